@@ -1,8 +1,12 @@
+use crate::token::Span;
+
 use super::Statement;
 
 pub trait Node {
     fn token_literal(&self) -> String;
     fn to_str(&self) -> String;
+    /// Location of the node in source code
+    fn span(&self) -> Span;
 }
 
 /// Root node of every AST
@@ -19,6 +23,13 @@ impl Node for Program {
         }
     }
 
+    fn span(&self) -> Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => Span::default(),
+        }
+    }
+
     fn to_str(&self) -> String {
         let mut sb = String::new();
 
@@ -30,10 +41,11 @@ impl Node for Program {
     }
 }
 
+#[cfg(test)]
 mod test {
     use crate::{
         ast::{ast::Node, Expression, Identifier, LetStatement},
-        token::{Kind, Token},
+        token::Span,
     };
 
     use super::{Program, Statement};
@@ -42,24 +54,15 @@ mod test {
     fn test_to_str() {
         // "let foo = bar;"
         let stmt = Statement::LetStatement(LetStatement {
-            token: Token {
-                kind: Kind::Let,
-                literal: "let".to_string(),
-            },
             name: Identifier {
-                token: Token {
-                    kind: Kind::Ident,
-                    literal: "foo".to_string(),
-                },
                 value: "foo".to_string(),
+                span: Span::default(),
             },
             value: Some(Expression::Identifier(Identifier {
-                token: Token {
-                    kind: Kind::Ident,
-                    literal: "bar".to_string(),
-                },
                 value: "bar".to_string(),
+                span: Span::default(),
             })),
+            span: Span::default(),
         });
 
         let program = Program {
@@ -1,13 +1,23 @@
-use crate::token::Token;
+use crate::token::{Kind, Span};
 
-use super::ast::Node;
+use super::{ast::Node, BlockStatement};
 
 #[derive(Debug, Clone)]
 pub enum Expression {
     Identifier(Identifier),
     IntegerLiteral(IntegerLiteral),
+    FloatLiteral(FloatLiteral),
     PrefixExpression(PrefixExpression),
     InfixExpression(InfixExpression),
+    Boolean(Boolean),
+    IfExpression(IfExpression),
+    FunctionLiteral(FunctionLiteral),
+    CallExpression(CallExpression),
+    StringLiteral(StringLiteral),
+    ArrayLiteral(ArrayLiteral),
+    IndexExpression(IndexExpression),
+    HashLiteral(HashLiteral),
+    Error(ErrorExpression),
 }
 
 impl Node for Expression {
@@ -15,8 +25,37 @@ impl Node for Expression {
         match self {
             Expression::Identifier(v) => v.token_literal(),
             Expression::IntegerLiteral(v) => v.token_literal(),
+            Expression::FloatLiteral(v) => v.token_literal(),
             Expression::PrefixExpression(v) => v.token_literal(),
             Expression::InfixExpression(v) => v.token_literal(),
+            Expression::Boolean(v) => v.token_literal(),
+            Expression::IfExpression(v) => v.token_literal(),
+            Expression::FunctionLiteral(v) => v.token_literal(),
+            Expression::CallExpression(v) => v.token_literal(),
+            Expression::StringLiteral(v) => v.token_literal(),
+            Expression::ArrayLiteral(v) => v.token_literal(),
+            Expression::IndexExpression(v) => v.token_literal(),
+            Expression::HashLiteral(v) => v.token_literal(),
+            Expression::Error(v) => v.token_literal(),
+        }
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            Expression::Identifier(v) => v.span(),
+            Expression::IntegerLiteral(v) => v.span(),
+            Expression::FloatLiteral(v) => v.span(),
+            Expression::PrefixExpression(v) => v.span(),
+            Expression::InfixExpression(v) => v.span(),
+            Expression::Boolean(v) => v.span(),
+            Expression::IfExpression(v) => v.span(),
+            Expression::FunctionLiteral(v) => v.span(),
+            Expression::CallExpression(v) => v.span(),
+            Expression::StringLiteral(v) => v.span(),
+            Expression::ArrayLiteral(v) => v.span(),
+            Expression::IndexExpression(v) => v.span(),
+            Expression::HashLiteral(v) => v.span(),
+            Expression::Error(v) => v.span(),
         }
     }
 
@@ -24,8 +63,18 @@ impl Node for Expression {
         match self {
             Expression::Identifier(v) => v.to_str(),
             Expression::IntegerLiteral(v) => v.to_str(),
+            Expression::FloatLiteral(v) => v.to_str(),
             Expression::PrefixExpression(v) => v.to_str(),
             Expression::InfixExpression(v) => v.to_str(),
+            Expression::Boolean(v) => v.to_str(),
+            Expression::IfExpression(v) => v.to_str(),
+            Expression::FunctionLiteral(v) => v.to_str(),
+            Expression::CallExpression(v) => v.to_str(),
+            Expression::StringLiteral(v) => v.to_str(),
+            Expression::ArrayLiteral(v) => v.to_str(),
+            Expression::IndexExpression(v) => v.to_str(),
+            Expression::HashLiteral(v) => v.to_str(),
+            Expression::Error(v) => v.to_str(),
         }
     }
 }
@@ -34,13 +83,17 @@ impl Node for Expression {
 // Example: foo;
 #[derive(Debug, Clone)]
 pub struct Identifier {
-    pub token: Token, // token.Ident
     pub value: String,
+    pub span: Span,
 }
 
 impl Node for Identifier {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.value.clone()
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_str(&self) -> String {
@@ -52,17 +105,45 @@ impl Node for Identifier {
 // Example: 5;
 #[derive(Debug, Clone)]
 pub struct IntegerLiteral {
-    pub token: Token, // token.Number
+    pub literal: String, // source spelling, printed back by to_str
     pub value: i64,
+    pub span: Span,
 }
 
 impl Node for IntegerLiteral {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.literal.clone()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        self.literal.clone()
+    }
+}
+
+// Float literal struct
+// Example: 3.14, 1e-9;
+#[derive(Debug, Clone)]
+pub struct FloatLiteral {
+    pub literal: String, // source spelling, printed back by to_str
+    pub value: f64,
+    pub span: Span,
+}
+
+impl Node for FloatLiteral {
+    fn token_literal(&self) -> String {
+        self.literal.clone()
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_str(&self) -> String {
-        self.token.literal.clone()
+        self.literal.clone()
     }
 }
 
@@ -70,23 +151,27 @@ impl Node for IntegerLiteral {
 // Example: !foo, -10;
 #[derive(Debug, Clone)]
 pub struct PrefixExpression {
-    pub token: Token, // prefix token: token.Minus, token.Bang
     pub operator: String,
     pub right: Option<Box<Expression>>,
+    pub span: Span,
 }
 
 impl Node for PrefixExpression {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.operator.clone()
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_str(&self) -> String {
         let mut sb = String::new();
 
-        sb.push_str("(");
+        sb.push('(');
         sb.push_str(&self.operator);
         sb.push_str(&self.right.as_ref().unwrap().to_str());
-        sb.push_str(")");
+        sb.push(')');
 
         sb
     }
@@ -96,28 +181,311 @@ impl Node for PrefixExpression {
 // Example: 5 + 5, 5 != 5;
 #[derive(Debug, Clone)]
 pub struct InfixExpression {
-    pub token: Token, // prefix token: token.Minus, token.Bang
     pub left: Option<Box<Expression>>,
     pub operator: String,
     pub right: Option<Box<Expression>>,
+    pub span: Span,
 }
 
 impl Node for InfixExpression {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.operator.clone()
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_str(&self) -> String {
         let mut sb = String::new();
 
-        sb.push_str("(");
+        sb.push('(');
         sb.push_str(&self.left.as_ref().unwrap().to_str());
-        sb.push_str(" ");
+        sb.push(' ');
         sb.push_str(&self.operator);
-        sb.push_str(" ");
+        sb.push(' ');
         sb.push_str(&self.right.as_ref().unwrap().to_str());
-        sb.push_str(")");
+        sb.push(')');
+
+        sb
+    }
+}
+
+// Boolean struct
+// Example: true;
+#[derive(Debug, Clone)]
+pub struct Boolean {
+    pub value: bool,
+    pub span: Span,
+}
+
+impl Node for Boolean {
+    fn token_literal(&self) -> String {
+        self.value.to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        self.value.to_string()
+    }
+}
+
+// If expression struct
+// Example: if (x < y) { x } else { y };
+#[derive(Debug, Clone)]
+pub struct IfExpression {
+    pub condition: Box<Expression>,
+    pub consequence: BlockStatement,
+    pub alternative: Option<BlockStatement>,
+    pub span: Span,
+}
+
+impl Node for IfExpression {
+    fn token_literal(&self) -> String {
+        Kind::If.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        sb.push_str("if ");
+        sb.push_str(&self.condition.to_str());
+        sb.push(' ');
+        sb.push_str(&self.consequence.to_str());
+
+        if let Some(alternative) = &self.alternative {
+            sb.push_str(" else ");
+            sb.push_str(&alternative.to_str());
+        }
 
         sb
     }
 }
+
+// Function literal struct
+// Example: function(x, y) { return x + y; };
+#[derive(Debug, Clone)]
+pub struct FunctionLiteral {
+    pub parameters: Vec<Identifier>,
+    pub body: BlockStatement,
+    pub span: Span,
+}
+
+impl Node for FunctionLiteral {
+    fn token_literal(&self) -> String {
+        Kind::Function.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.to_str()).collect();
+
+        sb.push_str(&self.token_literal());
+        sb.push('(');
+        sb.push_str(&parameters.join(", "));
+        sb.push_str(") ");
+        sb.push_str(&self.body.to_str());
+
+        sb
+    }
+}
+
+// Call expression struct
+// Example: add(1, 2 * 3);
+#[derive(Debug, Clone)]
+pub struct CallExpression {
+    pub function: Box<Expression>, // Identifier or FunctionLiteral
+    pub arguments: Vec<Expression>,
+    pub span: Span,
+}
+
+impl Node for CallExpression {
+    fn token_literal(&self) -> String {
+        Kind::Lparen.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        let arguments: Vec<String> = self.arguments.iter().map(|a| a.to_str()).collect();
+
+        sb.push_str(&self.function.to_str());
+        sb.push('(');
+        sb.push_str(&arguments.join(", "));
+        sb.push(')');
+
+        sb
+    }
+}
+
+// String literal struct
+// Example: "hello\tworld";
+#[derive(Debug, Clone)]
+pub struct StringLiteral {
+    pub value: String, // decoded value, without quotes and escapes
+    pub span: Span,
+}
+
+impl Node for StringLiteral {
+    fn token_literal(&self) -> String {
+        self.value.clone()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    // Quotes value and escapes special characters back
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        sb.push('"');
+
+        for ch in self.value.chars() {
+            match ch {
+                '\n' => sb.push_str("\\n"),
+                '\t' => sb.push_str("\\t"),
+                '\r' => sb.push_str("\\r"),
+                '"' => sb.push_str("\\\""),
+                '\\' => sb.push_str("\\\\"),
+                _ => sb.push(ch),
+            }
+        }
+
+        sb.push('"');
+
+        sb
+    }
+}
+
+// Array literal struct
+// Example: [1, 2 * 2, foo];
+#[derive(Debug, Clone)]
+pub struct ArrayLiteral {
+    pub elements: Vec<Expression>,
+    pub span: Span,
+}
+
+impl Node for ArrayLiteral {
+    fn token_literal(&self) -> String {
+        Kind::Lbracket.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        let elements: Vec<String> = self.elements.iter().map(|e| e.to_str()).collect();
+
+        sb.push('[');
+        sb.push_str(&elements.join(", "));
+        sb.push(']');
+
+        sb
+    }
+}
+
+// Index expression struct
+// Example: array[1 + 1];
+#[derive(Debug, Clone)]
+pub struct IndexExpression {
+    pub left: Box<Expression>,
+    pub index: Box<Expression>,
+    pub span: Span,
+}
+
+impl Node for IndexExpression {
+    fn token_literal(&self) -> String {
+        Kind::Lbracket.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        sb.push('(');
+        sb.push_str(&self.left.to_str());
+        sb.push('[');
+        sb.push_str(&self.index.to_str());
+        sb.push_str("])");
+
+        sb
+    }
+}
+
+// Hash literal struct
+// Example: {"name": "x", 1: true};
+#[derive(Debug, Clone)]
+pub struct HashLiteral {
+    pub pairs: Vec<(Expression, Expression)>, // key-value pairs in source order
+    pub span: Span,                           // from the first to the last token
+}
+
+impl Node for HashLiteral {
+    fn token_literal(&self) -> String {
+        Kind::Lbrace.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        let pairs: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key.to_str(), value.to_str()))
+            .collect();
+
+        sb.push('{');
+        sb.push_str(&pairs.join(", "));
+        sb.push('}');
+
+        sb
+    }
+}
+
+// Placeholder for expression which failed to parse.
+// Covers tokens skipped during error recovery
+#[derive(Debug, Clone)]
+pub struct ErrorExpression {
+    pub span: Span,
+}
+
+impl Node for ErrorExpression {
+    fn token_literal(&self) -> String {
+        "".to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        String::from("<error>")
+    }
+}
@@ -1,12 +1,14 @@
-use crate::token::Token;
+use crate::token::{Kind, Span};
 
 use super::{ast::Node, Expression, Identifier};
 
-#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone)]
 pub enum Statement {
     LetStatement(LetStatement),
     ReturnStatement(ReturnStatement),
     ExpressionStatement(ExpressionStatement),
+    Error(ErrorStatement),
 }
 
 impl Node for Statement {
@@ -15,6 +17,16 @@ impl Node for Statement {
             Statement::LetStatement(v) => v.token_literal(),
             Statement::ReturnStatement(v) => v.token_literal(),
             Statement::ExpressionStatement(v) => v.token_literal(),
+            Statement::Error(v) => v.token_literal(),
+        }
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            Statement::LetStatement(v) => v.span(),
+            Statement::ReturnStatement(v) => v.span(),
+            Statement::ExpressionStatement(v) => v.span(),
+            Statement::Error(v) => v.span(),
         }
     }
 
@@ -23,27 +35,32 @@ impl Node for Statement {
             Statement::LetStatement(v) => v.to_str(),
             Statement::ReturnStatement(v) => v.to_str(),
             Statement::ExpressionStatement(v) => v.to_str(),
+            Statement::Error(v) => v.to_str(),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LetStatement {
-    pub token: Token, // token.Let
     pub name: Identifier,
     pub value: Option<Expression>,
+    pub span: Span,
 }
 
 impl Node for LetStatement {
     fn token_literal(&self) -> String {
-        return self.token.literal.clone();
+        Kind::Let.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_str(&self) -> String {
         let mut sb = String::new();
 
         sb.push_str(&self.token_literal());
-        sb.push_str(" ");
+        sb.push(' ');
         sb.push_str(&self.name.to_str());
         sb.push_str(" = ");
 
@@ -51,34 +68,38 @@ impl Node for LetStatement {
             sb.push_str(&value.to_str())
         }
 
-        sb.push_str(";");
+        sb.push(';');
 
         sb
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReturnStatement {
-    pub token: Token, // token.Return
     pub return_value: Option<Expression>,
+    pub span: Span,
 }
 
 impl Node for ReturnStatement {
     fn token_literal(&self) -> String {
-        return self.token.literal.clone();
+        Kind::Return.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_str(&self) -> String {
         let mut sb = String::new();
 
         sb.push_str(&self.token_literal());
-        sb.push_str(" ");
+        sb.push(' ');
 
         if let Some(return_value) = &self.return_value {
             sb.push_str(&return_value.to_str());
         }
 
-        sb.push_str(";");
+        sb.push(';');
 
         sb
     }
@@ -88,15 +109,22 @@ impl Node for ReturnStatement {
 // Example:
 // let x = 5;
 // x + 10; <- ExpressionStatement
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExpressionStatement {
-    pub token: Token, // first token of expression
     pub expression: Option<Expression>,
+    pub span: Span,
 }
 
 impl Node for ExpressionStatement {
     fn token_literal(&self) -> String {
-        return self.token.literal.clone();
+        match &self.expression {
+            Some(expr) => expr.token_literal(),
+            None => "".to_string(),
+        }
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_str(&self) -> String {
@@ -109,3 +137,60 @@ impl Node for ExpressionStatement {
         sb
     }
 }
+
+// Placeholder for statement which failed to parse.
+// Covers tokens skipped during error recovery
+#[derive(Debug, Clone)]
+pub struct ErrorStatement {
+    pub span: Span,
+}
+
+impl Node for ErrorStatement {
+    fn token_literal(&self) -> String {
+        "".to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        String::from("<error>;")
+    }
+}
+
+// Block statement struct
+// Example:
+// if (x) { <- BlockStatement
+//     let y = x;
+//     y + 1;
+// }
+#[derive(Debug, Clone)]
+pub struct BlockStatement {
+    pub statements: Vec<Statement>,
+    pub span: Span,
+}
+
+impl Node for BlockStatement {
+    fn token_literal(&self) -> String {
+        Kind::Lbrace.to_str().to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_str(&self) -> String {
+        let mut sb = String::new();
+
+        sb.push_str("{ ");
+
+        for statement in &self.statements {
+            sb.push_str(&statement.to_str());
+        }
+
+        sb.push_str(" }");
+
+        sb
+    }
+}
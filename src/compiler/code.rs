@@ -0,0 +1,269 @@
+use crate::token::Span;
+
+/// Flat sequence of encoded instructions
+pub type Instructions = Vec<u8>;
+
+/// Offset of every instruction with location of the node it was compiled from, in offset order
+pub type SourceMap = Vec<(usize, Span)>;
+
+/// Instruction opcode. Operands follow opcode byte in big-endian order
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Constant, // push constant: constant index
+    Pop,      // pop result of expression statement
+
+    // binary operators, pop right and left operands
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Equal,
+    NotEqual,
+    LessThan,
+    GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual,
+
+    // prefix operators
+    Minus,
+    Bang,
+
+    True,
+    False,
+    Null,
+
+    Jump,          // jump: target offset
+    JumpNotTruthy, // pop condition and jump if it is falsy: target offset
+
+    GetGlobal,      // push global binding: global index
+    SetGlobal,      // pop value into global binding: global index
+    GetLocal,       // push local binding of current frame: local index
+    SetLocal,       // pop value into local binding of current frame: local index
+    GetBuiltin,     // push builtin function: builtin index
+    GetFree,        // push variable captured by current closure: free variable index
+    Closure,        // wrap compiled function into closure: constant index, free variables count
+    CurrentClosure, // push closure being executed, used for recursive calls
+    Call,           // call function below arguments: arguments count
+    ReturnValue,    // return value on top of the stack from current function
+}
+
+/// All opcodes ordered by their byte value
+const OPCODES: [Opcode; 31] = [
+    Opcode::Constant,
+    Opcode::Pop,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::Mod,
+    Opcode::Pow,
+    Opcode::Equal,
+    Opcode::NotEqual,
+    Opcode::LessThan,
+    Opcode::GreaterThan,
+    Opcode::LessThanOrEqual,
+    Opcode::GreaterThanOrEqual,
+    Opcode::Minus,
+    Opcode::Bang,
+    Opcode::True,
+    Opcode::False,
+    Opcode::Null,
+    Opcode::Jump,
+    Opcode::JumpNotTruthy,
+    Opcode::GetGlobal,
+    Opcode::SetGlobal,
+    Opcode::GetLocal,
+    Opcode::SetLocal,
+    Opcode::GetBuiltin,
+    Opcode::GetFree,
+    Opcode::Closure,
+    Opcode::CurrentClosure,
+    Opcode::Call,
+    Opcode::ReturnValue,
+];
+
+impl Opcode {
+    pub fn from_byte(byte: u8) -> Option<Opcode> {
+        OPCODES.get(byte as usize).copied()
+    }
+
+    /// Returns mnemonic used in disassembly
+    pub fn name(self) -> &'static str {
+        match self {
+            Opcode::Constant => "OpConstant",
+            Opcode::Pop => "OpPop",
+            Opcode::Add => "OpAdd",
+            Opcode::Sub => "OpSub",
+            Opcode::Mul => "OpMul",
+            Opcode::Div => "OpDiv",
+            Opcode::Mod => "OpMod",
+            Opcode::Pow => "OpPow",
+            Opcode::Equal => "OpEqual",
+            Opcode::NotEqual => "OpNotEqual",
+            Opcode::LessThan => "OpLessThan",
+            Opcode::GreaterThan => "OpGreaterThan",
+            Opcode::LessThanOrEqual => "OpLessThanOrEqual",
+            Opcode::GreaterThanOrEqual => "OpGreaterThanOrEqual",
+            Opcode::Minus => "OpMinus",
+            Opcode::Bang => "OpBang",
+            Opcode::True => "OpTrue",
+            Opcode::False => "OpFalse",
+            Opcode::Null => "OpNull",
+            Opcode::Jump => "OpJump",
+            Opcode::JumpNotTruthy => "OpJumpNotTruthy",
+            Opcode::GetGlobal => "OpGetGlobal",
+            Opcode::SetGlobal => "OpSetGlobal",
+            Opcode::GetLocal => "OpGetLocal",
+            Opcode::SetLocal => "OpSetLocal",
+            Opcode::GetBuiltin => "OpGetBuiltin",
+            Opcode::GetFree => "OpGetFree",
+            Opcode::Closure => "OpClosure",
+            Opcode::CurrentClosure => "OpCurrentClosure",
+            Opcode::Call => "OpCall",
+            Opcode::ReturnValue => "OpReturnValue",
+        }
+    }
+
+    /// Returns width in bytes of each operand
+    pub fn operand_widths(self) -> &'static [usize] {
+        match self {
+            Opcode::Constant
+            | Opcode::Jump
+            | Opcode::JumpNotTruthy
+            | Opcode::GetGlobal
+            | Opcode::SetGlobal => &[2],
+            Opcode::GetLocal
+            | Opcode::SetLocal
+            | Opcode::GetBuiltin
+            | Opcode::GetFree
+            | Opcode::Call => &[1],
+            Opcode::Closure => &[2, 1],
+            _ => &[],
+        }
+    }
+}
+
+/// Returns the largest operand which fits into width
+pub fn max_operand(width: usize) -> usize {
+    match width {
+        1 => u8::MAX as usize,
+        2 => u16::MAX as usize,
+        _ => unreachable!("unsupported operand width {}", width),
+    }
+}
+
+/// Encodes instruction: opcode byte followed by operands.
+/// Panics if operand doesn't fit its width, callers should check it with `max_operand`
+pub fn make(op: Opcode, operands: &[usize]) -> Instructions {
+    let widths = op.operand_widths();
+
+    let mut instruction = Vec::with_capacity(1 + widths.iter().sum::<usize>());
+    instruction.push(op as u8);
+
+    for (operand, width) in operands.iter().zip(widths) {
+        match width {
+            1 => instruction.push(u8::try_from(*operand).expect("operand overflow")),
+            2 => instruction.extend_from_slice(
+                &u16::try_from(*operand)
+                    .expect("operand overflow")
+                    .to_be_bytes(),
+            ),
+            _ => unreachable!("unsupported operand width {}", width),
+        }
+    }
+
+    instruction
+}
+
+/// Decodes operands of instruction. Instructions should start right after opcode.
+/// Returns operands and number of bytes read
+pub fn read_operands(op: Opcode, ins: &[u8]) -> (Vec<usize>, usize) {
+    let mut operands = Vec::with_capacity(op.operand_widths().len());
+    let mut offset = 0;
+
+    for width in op.operand_widths() {
+        match width {
+            1 => operands.push(ins[offset] as usize),
+            2 => operands.push(read_u16(&ins[offset..]) as usize),
+            _ => unreachable!("unsupported operand width {}", width),
+        }
+
+        offset += width;
+    }
+
+    (operands, offset)
+}
+
+pub fn read_u16(ins: &[u8]) -> u16 {
+    u16::from_be_bytes([ins[0], ins[1]])
+}
+
+#[cfg(test)]
+mod test {
+    use super::{make, read_operands, Opcode, OPCODES};
+
+    #[test]
+    fn test_make() {
+        let tests = vec![
+            (
+                Opcode::Constant,
+                vec![65534],
+                vec![Opcode::Constant as u8, 255, 254],
+            ),
+            (Opcode::Add, vec![], vec![Opcode::Add as u8]),
+            (
+                Opcode::JumpNotTruthy,
+                vec![7],
+                vec![Opcode::JumpNotTruthy as u8, 0, 7],
+            ),
+            (
+                Opcode::GetLocal,
+                vec![255],
+                vec![Opcode::GetLocal as u8, 255],
+            ),
+            (
+                Opcode::Closure,
+                vec![65534, 255],
+                vec![Opcode::Closure as u8, 255, 254, 255],
+            ),
+        ];
+
+        for (op, operands, expected) in tests {
+            assert_eq!(make(op, &operands), expected, "opcode: {:?}", op);
+        }
+    }
+
+    #[test]
+    fn test_read_operands() {
+        let tests = vec![
+            (Opcode::Constant, vec![65535], 2),
+            (Opcode::Pop, vec![], 0),
+            (Opcode::GetLocal, vec![255], 1),
+            (Opcode::Closure, vec![65535, 255], 3),
+        ];
+
+        for (op, operands, bytes_read) in tests {
+            let instruction = make(op, &operands);
+
+            assert_eq!(
+                read_operands(op, &instruction[1..]),
+                (operands, bytes_read),
+                "opcode: {:?}",
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn test_opcode_bytes() {
+        for (byte, op) in OPCODES.iter().enumerate() {
+            assert_eq!(*op as usize, byte, "opcode: {:?}", op);
+            assert_eq!(Opcode::from_byte(byte as u8), Some(*op));
+        }
+
+        assert_eq!(Opcode::from_byte(OPCODES.len() as u8), None);
+    }
+}
@@ -0,0 +1,1008 @@
+use std::{mem, rc::Rc};
+
+use crate::{
+    ast::{
+        ast::{Node, Program},
+        BlockStatement, CallExpression, Expression, FunctionLiteral, IfExpression, InfixExpression,
+        LetStatement, Statement,
+    },
+    object::{CompiledFunction, Object, BUILTINS},
+    token::Span,
+};
+
+use super::{
+    make, max_operand, CompileError, Instructions, Opcode, SourceMap, Symbol, SymbolScope,
+    SymbolTable,
+};
+
+// limits of 1 byte wide operands
+const MAX_LOCALS: usize = 256; // OpGetLocal and OpSetLocal index
+const MAX_FREE_VARIABLES: usize = 255; // count operand of OpClosure
+const MAX_ARGUMENTS: usize = 255; // OpCall
+
+/// Compiled program: instructions and constants they refer to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bytecode {
+    pub instructions: Instructions,
+    pub constants: Vec<Object>,
+    pub source_map: SourceMap,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EmittedInstruction {
+    opcode: Opcode,
+    position: usize,
+}
+
+/// Instructions of the function being compiled
+#[derive(Default)]
+struct CompilationScope {
+    instructions: Instructions,
+    source_map: SourceMap,
+    last_instruction: Option<EmittedInstruction>,
+    previous_instruction: Option<EmittedInstruction>,
+}
+
+/// Lowers AST to bytecode for the virtual machine
+pub struct Compiler {
+    constants: Vec<Object>, // constant pool
+    symbol_table: SymbolTable,
+
+    scopes: Vec<CompilationScope>, // the first one is the main program, the last one is current
+    span: Span,                    // node being compiled, recorded for emitted instructions
+
+    // globals used in function bodies before their let statement, defined by the end of program
+    forward_globals: Vec<(String, Span)>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        let mut symbol_table = SymbolTable::new();
+
+        for (index, (name, _)) in BUILTINS.iter().enumerate() {
+            symbol_table.define_builtin(index, name);
+        }
+
+        Compiler {
+            constants: Vec::new(),
+            symbol_table,
+            scopes: vec![CompilationScope::default()],
+            span: Span::default(),
+            forward_globals: Vec::new(),
+        }
+    }
+
+    pub fn compile(&mut self, program: &Program) -> Result<(), CompileError> {
+        for statement in &program.statements {
+            self.compile_statement(statement)?;
+        }
+
+        match self.forward_globals.first() {
+            Some((name, span)) => Err(CompileError::UndefinedVariable {
+                name: name.clone(),
+                span: *span,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn bytecode(mut self) -> Bytecode {
+        let main = mem::take(&mut self.scopes[0]);
+
+        Bytecode {
+            instructions: main.instructions,
+            constants: self.constants,
+            source_map: main.source_map,
+        }
+    }
+
+    /// Compiles statement. Emitted instructions are mapped to it, unless inner node is more precise
+    fn compile_statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        let outer_span = mem::replace(&mut self.span, statement.span());
+        let result = self.compile_statement_node(statement);
+        self.span = outer_span;
+
+        result
+    }
+
+    fn compile_statement_node(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        match statement {
+            Statement::ExpressionStatement(stmt) => {
+                if let Some(expr) = &stmt.expression {
+                    self.compile_expression(expr)?;
+                    self.emit(Opcode::Pop, &[])?;
+                }
+
+                Ok(())
+            }
+            Statement::LetStatement(stmt) => self.compile_let_statement(stmt),
+            Statement::ReturnStatement(stmt) => {
+                match &stmt.return_value {
+                    Some(value) => self.compile_expression(value)?,
+                    None => {
+                        self.emit(Opcode::Null, &[])?;
+                    }
+                }
+
+                self.emit(Opcode::ReturnValue, &[])?;
+
+                Ok(())
+            }
+            Statement::Error(stmt) => unsupported("invalid statement", stmt.span),
+        }
+    }
+
+    fn compile_expression(&mut self, expression: &Expression) -> Result<(), CompileError> {
+        let outer_span = mem::replace(&mut self.span, expression.span());
+        let result = self.compile_expression_node(expression);
+        self.span = outer_span;
+
+        result
+    }
+
+    fn compile_expression_node(&mut self, expression: &Expression) -> Result<(), CompileError> {
+        match expression {
+            Expression::IntegerLiteral(literal) => {
+                let index = self.add_constant(Object::Integer(literal.value));
+                self.emit(Opcode::Constant, &[index])?;
+            }
+            Expression::FloatLiteral(literal) => {
+                let index = self.add_constant(Object::Float(literal.value));
+                self.emit(Opcode::Constant, &[index])?;
+            }
+            Expression::StringLiteral(literal) => {
+                let index = self.add_constant(Object::String(literal.value.clone()));
+                self.emit(Opcode::Constant, &[index])?;
+            }
+            Expression::Boolean(literal) => {
+                let opcode = if literal.value {
+                    Opcode::True
+                } else {
+                    Opcode::False
+                };
+                self.emit(opcode, &[])?;
+            }
+            Expression::PrefixExpression(expr) => {
+                let right = match expr.right.as_deref() {
+                    Some(right) => right,
+                    None => return unsupported("invalid expression", expr.span),
+                };
+
+                self.compile_expression(right)?;
+
+                match expr.operator.as_str() {
+                    "-" => self.emit(Opcode::Minus, &[])?,
+                    "!" => self.emit(Opcode::Bang, &[])?,
+                    _ => return unsupported("prefix operator", expr.span),
+                };
+            }
+            Expression::InfixExpression(expr) => self.compile_infix_expression(expr)?,
+            Expression::IfExpression(expr) => self.compile_if_expression(expr)?,
+            Expression::Identifier(ident) => {
+                let in_function = self.scopes.len() > 1;
+                let defined_later = || {
+                    self.forward_globals
+                        .iter()
+                        .any(|(name, _)| *name == ident.value)
+                };
+
+                let symbol = match self.symbol_table.resolve(&ident.value) {
+                    Some(symbol) if in_function || !defined_later() => symbol,
+                    // function body may call global defined after it, e.g. for mutual recursion
+                    None if in_function => {
+                        self.forward_globals
+                            .push((ident.value.clone(), ident.span()));
+                        self.symbol_table.define_global(&ident.value)
+                    }
+                    _ => {
+                        return Err(CompileError::UndefinedVariable {
+                            name: ident.value.clone(),
+                            span: ident.span(),
+                        })
+                    }
+                };
+
+                if symbol.scope == SymbolScope::Free && symbol.index >= MAX_FREE_VARIABLES {
+                    return Err(too_many("free variables", MAX_FREE_VARIABLES, ident.span()));
+                }
+
+                self.load_symbol(&symbol)?;
+            }
+            Expression::FunctionLiteral(literal) => self.compile_function_literal(literal, None)?,
+            Expression::CallExpression(expr) => self.compile_call_expression(expr)?,
+            Expression::ArrayLiteral(literal) => return unsupported("array literal", literal.span),
+            Expression::HashLiteral(literal) => return unsupported("hash literal", literal.span),
+            Expression::IndexExpression(expr) => return unsupported("index expression", expr.span),
+            Expression::Error(expr) => return unsupported("invalid expression", expr.span),
+        }
+
+        Ok(())
+    }
+
+    /// Binds value to global or local slot. Function values can refer to themselves by name
+    fn compile_let_statement(&mut self, stmt: &LetStatement) -> Result<(), CompileError> {
+        match &stmt.value {
+            Some(Expression::FunctionLiteral(literal)) => {
+                self.compile_function_literal(literal, Some(&stmt.name.value))?
+            }
+            Some(value) => self.compile_expression(value)?,
+            None => {
+                self.emit(Opcode::Null, &[])?;
+            }
+        }
+
+        // defined after value is compiled, so "let x = x" refers to outer binding
+        let symbol = self.symbol_table.define(&stmt.name.value);
+
+        if symbol.scope == SymbolScope::Local && symbol.index >= MAX_LOCALS {
+            return Err(too_many("local variables", MAX_LOCALS, stmt.span));
+        }
+
+        if symbol.scope == SymbolScope::Global {
+            self.forward_globals
+                .retain(|(name, _)| *name != stmt.name.value);
+        }
+
+        match symbol.scope {
+            SymbolScope::Global => self.emit(Opcode::SetGlobal, &[symbol.index])?,
+            _ => self.emit(Opcode::SetLocal, &[symbol.index])?,
+        };
+
+        Ok(())
+    }
+
+    /// Compiles function body in its own scope and emits closure capturing its free variables
+    fn compile_function_literal(
+        &mut self,
+        literal: &FunctionLiteral,
+        name: Option<&str>,
+    ) -> Result<(), CompileError> {
+        if literal.parameters.len() > MAX_ARGUMENTS {
+            return Err(too_many("parameters", MAX_ARGUMENTS, literal.span));
+        }
+
+        self.enter_scope();
+
+        if let Some(name) = name {
+            self.symbol_table.define_function_name(name);
+        }
+
+        for parameter in &literal.parameters {
+            self.symbol_table.define(&parameter.value);
+        }
+
+        self.compile_block(&literal.body)?;
+        self.emit(Opcode::ReturnValue, &[])?;
+
+        let (scope, symbol_table) = self.leave_scope();
+
+        // inner functions may capture more variables than this function refers to itself
+        if symbol_table.free_symbols().len() > MAX_FREE_VARIABLES {
+            return Err(too_many("free variables", MAX_FREE_VARIABLES, literal.span));
+        }
+
+        // captured values are pushed by enclosing function, so they are loaded in its scope
+        for symbol in symbol_table.free_symbols() {
+            self.load_symbol(symbol)?;
+        }
+
+        let function = CompiledFunction {
+            instructions: scope.instructions,
+            source_map: scope.source_map,
+            num_locals: symbol_table.num_definitions(),
+            num_parameters: literal.parameters.len(),
+        };
+
+        let index = self.add_constant(Object::CompiledFunction(Rc::new(function)));
+        self.emit(Opcode::Closure, &[index, symbol_table.free_symbols().len()])?;
+
+        Ok(())
+    }
+
+    fn compile_call_expression(&mut self, expr: &CallExpression) -> Result<(), CompileError> {
+        if expr.arguments.len() > MAX_ARGUMENTS {
+            return Err(too_many("arguments", MAX_ARGUMENTS, expr.span));
+        }
+
+        self.compile_expression(&expr.function)?;
+
+        for argument in &expr.arguments {
+            self.compile_expression(argument)?;
+        }
+
+        self.emit(Opcode::Call, &[expr.arguments.len()])?;
+
+        Ok(())
+    }
+
+    fn compile_infix_expression(&mut self, expr: &InfixExpression) -> Result<(), CompileError> {
+        let (left, right) = match (expr.left.as_deref(), expr.right.as_deref()) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return unsupported("invalid expression", expr.span),
+        };
+
+        match expr.operator.as_str() {
+            "&&" => return self.compile_logical_and(left, right),
+            "||" => return self.compile_logical_or(left, right),
+            _ => {}
+        }
+
+        let opcode = match expr.operator.as_str() {
+            "+" => Opcode::Add,
+            "-" => Opcode::Sub,
+            "*" => Opcode::Mul,
+            "/" => Opcode::Div,
+            "%" => Opcode::Mod,
+            "**" => Opcode::Pow,
+            "==" => Opcode::Equal,
+            "!=" => Opcode::NotEqual,
+            "<" => Opcode::LessThan,
+            ">" => Opcode::GreaterThan,
+            "<=" => Opcode::LessThanOrEqual,
+            ">=" => Opcode::GreaterThanOrEqual,
+            _ => return unsupported("infix operator", expr.span),
+        };
+
+        self.compile_expression(left)?;
+        self.compile_expression(right)?;
+        self.emit(opcode, &[])?;
+
+        Ok(())
+    }
+
+    /// Compiles "left && right". Right side is skipped if left one is falsy
+    fn compile_logical_and(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<(), CompileError> {
+        self.compile_expression(left)?;
+        let left_jump = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+        self.compile_expression(right)?;
+        let right_jump = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+        self.emit(Opcode::True, &[])?;
+        let end_jump = self.emit(Opcode::Jump, &[0])?;
+
+        let false_position = self.scope().instructions.len();
+        self.change_operand(left_jump, false_position)?;
+        self.change_operand(right_jump, false_position)?;
+        self.emit(Opcode::False, &[])?;
+
+        self.change_operand(end_jump, self.scope().instructions.len())?;
+
+        Ok(())
+    }
+
+    /// Compiles "left || right". Right side is skipped if left one is truthy
+    fn compile_logical_or(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<(), CompileError> {
+        self.compile_expression(left)?;
+        let left_jump = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+        self.emit(Opcode::True, &[])?;
+        let true_jump = self.emit(Opcode::Jump, &[0])?;
+
+        self.change_operand(left_jump, self.scope().instructions.len())?;
+        self.compile_expression(right)?;
+        let right_jump = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+        self.emit(Opcode::True, &[])?;
+        let end_jump = self.emit(Opcode::Jump, &[0])?;
+
+        self.change_operand(right_jump, self.scope().instructions.len())?;
+        self.emit(Opcode::False, &[])?;
+
+        let end_position = self.scope().instructions.len();
+        self.change_operand(true_jump, end_position)?;
+        self.change_operand(end_jump, end_position)?;
+
+        Ok(())
+    }
+
+    /// Compiles conditional. Jump targets are patched once branches are emitted
+    fn compile_if_expression(&mut self, expr: &IfExpression) -> Result<(), CompileError> {
+        self.compile_expression(&expr.condition)?;
+
+        let condition_jump = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+        self.compile_block(&expr.consequence)?;
+
+        let end_jump = self.emit(Opcode::Jump, &[0])?;
+
+        self.change_operand(condition_jump, self.scope().instructions.len())?;
+
+        match &expr.alternative {
+            Some(alternative) => self.compile_block(alternative)?,
+            None => {
+                self.emit(Opcode::Null, &[])?;
+            }
+        }
+
+        self.change_operand(end_jump, self.scope().instructions.len())?;
+
+        Ok(())
+    }
+
+    /// Compiles block, which leaves value of the last statement on the stack.
+    /// Block without expression at the end produces null
+    fn compile_block(&mut self, block: &BlockStatement) -> Result<(), CompileError> {
+        let start = self.scope().instructions.len();
+
+        for statement in &block.statements {
+            self.compile_statement(statement)?;
+        }
+
+        if self.scope().instructions.len() > start && self.is_last_instruction(Opcode::Pop) {
+            self.remove_last_pop();
+        } else {
+            self.emit(Opcode::Null, &[])?;
+        }
+
+        Ok(())
+    }
+
+    /// Emits instruction which pushes value of the symbol
+    fn load_symbol(&mut self, symbol: &Symbol) -> Result<(), CompileError> {
+        match symbol.scope {
+            SymbolScope::Global => self.emit(Opcode::GetGlobal, &[symbol.index])?,
+            SymbolScope::Local => self.emit(Opcode::GetLocal, &[symbol.index])?,
+            SymbolScope::Builtin => self.emit(Opcode::GetBuiltin, &[symbol.index])?,
+            SymbolScope::Free => self.emit(Opcode::GetFree, &[symbol.index])?,
+            SymbolScope::Function => self.emit(Opcode::CurrentClosure, &[])?,
+        };
+
+        Ok(())
+    }
+
+    /// Starts compiling function body: new instructions and enclosed symbol table
+    fn enter_scope(&mut self) {
+        self.scopes.push(CompilationScope::default());
+
+        let outer = mem::take(&mut self.symbol_table);
+        self.symbol_table = SymbolTable::new_enclosed(outer);
+    }
+
+    /// Finishes function body. Returns its instructions and symbol table
+    fn leave_scope(&mut self) -> (CompilationScope, SymbolTable) {
+        let scope = self.scopes.pop().expect("no compilation scope");
+
+        let mut symbol_table = mem::take(&mut self.symbol_table);
+        self.symbol_table = symbol_table.take_outer().expect("no outer symbol table");
+
+        (scope, symbol_table)
+    }
+
+    fn scope(&self) -> &CompilationScope {
+        self.scopes.last().expect("no compilation scope")
+    }
+
+    fn scope_mut(&mut self) -> &mut CompilationScope {
+        self.scopes.last_mut().expect("no compilation scope")
+    }
+
+    /// Adds constant to the pool and returns its index
+    fn add_constant(&mut self, object: Object) -> usize {
+        self.constants.push(object);
+        self.constants.len() - 1
+    }
+
+    /// Appends instruction and returns its position.
+    /// Fails if operand doesn't fit its width, e.g. constant index above 65535
+    fn emit(&mut self, opcode: Opcode, operands: &[usize]) -> Result<usize, CompileError> {
+        self.check_operands(opcode, operands)?;
+
+        let span = self.span;
+
+        let scope = self.scope_mut();
+        let position = scope.instructions.len();
+
+        scope.instructions.extend(make(opcode, operands));
+        scope.source_map.push((position, span));
+
+        scope.previous_instruction = scope.last_instruction;
+        scope.last_instruction = Some(EmittedInstruction { opcode, position });
+
+        Ok(position)
+    }
+
+    /// Replaces operand of instruction at position. Used to patch jump targets
+    fn change_operand(&mut self, position: usize, operand: usize) -> Result<(), CompileError> {
+        let opcode =
+            Opcode::from_byte(self.scope().instructions[position]).expect("invalid opcode");
+
+        self.check_operands(opcode, &[operand])?;
+
+        let instruction = make(opcode, &[operand]);
+        let instructions = &mut self.scope_mut().instructions;
+
+        instructions[position..position + instruction.len()].copy_from_slice(&instruction);
+
+        Ok(())
+    }
+
+    fn check_operands(&self, opcode: Opcode, operands: &[usize]) -> Result<(), CompileError> {
+        for (operand, width) in operands.iter().zip(opcode.operand_widths()) {
+            let max = max_operand(*width);
+
+            if *operand > max {
+                return Err(CompileError::OperandOverflow {
+                    opcode: opcode.name(),
+                    operand: *operand,
+                    max,
+                    span: self.span,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_last_instruction(&self, opcode: Opcode) -> bool {
+        matches!(self.scope().last_instruction, Some(last) if last.opcode == opcode)
+    }
+
+    fn remove_last_pop(&mut self) {
+        let scope = self.scope_mut();
+
+        if let Some(last) = scope.last_instruction {
+            scope.instructions.truncate(last.position);
+            scope.source_map.pop();
+            scope.last_instruction = scope.previous_instruction;
+        }
+    }
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Compiler::new()
+    }
+}
+
+fn unsupported(node: &'static str, span: Span) -> Result<(), CompileError> {
+    Err(CompileError::UnsupportedNode { node, span })
+}
+
+fn too_many(what: &'static str, max: usize, span: Span) -> CompileError {
+    CompileError::TooMany { what, max, span }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use crate::{
+        compiler::{make, Instructions, Opcode},
+        object::{CompiledFunction, Object},
+        parser::parse,
+    };
+
+    use super::{Bytecode, Compiler};
+
+    fn compile(input: &str) -> Bytecode {
+        let program = parse(input);
+
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).expect("compile failed");
+
+        compiler.bytecode()
+    }
+
+    fn concat(instructions: Vec<Instructions>) -> Instructions {
+        instructions.into_iter().flatten().collect()
+    }
+
+    fn function(
+        instructions: Vec<Instructions>,
+        num_locals: usize,
+        num_parameters: usize,
+    ) -> Object {
+        Object::CompiledFunction(Rc::new(CompiledFunction {
+            instructions: concat(instructions),
+            source_map: Vec::new(),
+            num_locals,
+            num_parameters,
+        }))
+    }
+
+    #[test]
+    fn test_integer_arithmetic() {
+        let tests = vec![
+            (
+                "1 + 2",
+                vec![1, 2],
+                vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Constant, &[1]),
+                    make(Opcode::Add, &[]),
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+            (
+                "1; 2",
+                vec![1, 2],
+                vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Pop, &[]),
+                    make(Opcode::Constant, &[1]),
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+            (
+                "2 < 1",
+                vec![2, 1],
+                vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Constant, &[1]),
+                    make(Opcode::LessThan, &[]),
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+            (
+                "-1",
+                vec![1],
+                vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Minus, &[]),
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+            (
+                "!true",
+                vec![],
+                vec![
+                    make(Opcode::True, &[]),
+                    make(Opcode::Bang, &[]),
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+        ];
+
+        for (input, constants, instructions) in tests {
+            let bytecode = compile(input);
+
+            let constants: Vec<Object> = constants.into_iter().map(Object::Integer).collect();
+
+            assert_eq!(
+                bytecode.instructions,
+                concat(instructions),
+                "input: {}",
+                input
+            );
+            assert_eq!(bytecode.constants, constants, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let tests = vec![
+            (
+                "if (true) { 10 }; 3333;",
+                vec![
+                    // 0000
+                    make(Opcode::True, &[]),
+                    // 0001
+                    make(Opcode::JumpNotTruthy, &[10]),
+                    // 0004
+                    make(Opcode::Constant, &[0]),
+                    // 0007
+                    make(Opcode::Jump, &[11]),
+                    // 0010
+                    make(Opcode::Null, &[]),
+                    // 0011
+                    make(Opcode::Pop, &[]),
+                    // 0012
+                    make(Opcode::Constant, &[1]),
+                    // 0015
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+            (
+                "if (true) { 10 } else { 20 }; 3333;",
+                vec![
+                    // 0000
+                    make(Opcode::True, &[]),
+                    // 0001
+                    make(Opcode::JumpNotTruthy, &[10]),
+                    // 0004
+                    make(Opcode::Constant, &[0]),
+                    // 0007
+                    make(Opcode::Jump, &[13]),
+                    // 0010
+                    make(Opcode::Constant, &[1]),
+                    // 0013
+                    make(Opcode::Pop, &[]),
+                    // 0014
+                    make(Opcode::Constant, &[2]),
+                    // 0017
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+            (
+                "if (true) { }",
+                vec![
+                    // 0000
+                    make(Opcode::True, &[]),
+                    // 0001
+                    make(Opcode::JumpNotTruthy, &[8]),
+                    // 0004
+                    make(Opcode::Null, &[]),
+                    // 0005
+                    make(Opcode::Jump, &[9]),
+                    // 0008
+                    make(Opcode::Null, &[]),
+                    // 0009
+                    make(Opcode::Pop, &[]),
+                ],
+            ),
+        ];
+
+        for (input, instructions) in tests {
+            let bytecode = compile(input);
+
+            assert_eq!(
+                bytecode.instructions,
+                concat(instructions),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_global_let_statements() {
+        let bytecode = compile("let one = 1; let two = one; two; len;");
+
+        let expected = vec![
+            make(Opcode::Constant, &[0]),
+            make(Opcode::SetGlobal, &[0]),
+            make(Opcode::GetGlobal, &[0]),
+            make(Opcode::SetGlobal, &[1]),
+            make(Opcode::GetGlobal, &[1]),
+            make(Opcode::Pop, &[]),
+            make(Opcode::GetBuiltin, &[0]),
+            make(Opcode::Pop, &[]),
+        ];
+
+        assert_eq!(bytecode.instructions, concat(expected));
+        assert_eq!(bytecode.constants, vec![Object::Integer(1)]);
+    }
+
+    #[test]
+    fn test_functions() {
+        let bytecode = compile("let add = function(a, b) { let c = a + b; c }; add(1, 2);");
+
+        let body = vec![
+            make(Opcode::GetLocal, &[0]),
+            make(Opcode::GetLocal, &[1]),
+            make(Opcode::Add, &[]),
+            make(Opcode::SetLocal, &[2]),
+            make(Opcode::GetLocal, &[2]),
+            make(Opcode::ReturnValue, &[]),
+        ];
+
+        let expected = vec![
+            make(Opcode::Closure, &[0, 0]),
+            make(Opcode::SetGlobal, &[0]),
+            make(Opcode::GetGlobal, &[0]),
+            make(Opcode::Constant, &[1]),
+            make(Opcode::Constant, &[2]),
+            make(Opcode::Call, &[2]),
+            make(Opcode::Pop, &[]),
+        ];
+
+        assert_eq!(bytecode.instructions, concat(expected));
+        assert_eq!(
+            bytecode.constants,
+            vec![function(body, 3, 2), Object::Integer(1), Object::Integer(2)]
+        );
+    }
+
+    #[test]
+    fn test_closures() {
+        let bytecode = compile("function(a) { function(b) { function(c) { a + b + c } } }");
+
+        let innermost = vec![
+            make(Opcode::GetFree, &[0]),
+            make(Opcode::GetFree, &[1]),
+            make(Opcode::Add, &[]),
+            make(Opcode::GetLocal, &[0]),
+            make(Opcode::Add, &[]),
+            make(Opcode::ReturnValue, &[]),
+        ];
+
+        let middle = vec![
+            make(Opcode::GetFree, &[0]),
+            make(Opcode::GetLocal, &[0]),
+            make(Opcode::Closure, &[0, 2]),
+            make(Opcode::ReturnValue, &[]),
+        ];
+
+        let outer = vec![
+            make(Opcode::GetLocal, &[0]),
+            make(Opcode::Closure, &[1, 1]),
+            make(Opcode::ReturnValue, &[]),
+        ];
+
+        let expected = vec![make(Opcode::Closure, &[2, 0]), make(Opcode::Pop, &[])];
+
+        assert_eq!(bytecode.instructions, concat(expected));
+        assert_eq!(
+            bytecode.constants,
+            vec![
+                function(innermost, 1, 1),
+                function(middle, 1, 1),
+                function(outer, 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recursive_functions() {
+        let bytecode = compile("let countdown = function(x) { return countdown(x - 1); };");
+
+        let body = vec![
+            make(Opcode::CurrentClosure, &[]),
+            make(Opcode::GetLocal, &[0]),
+            make(Opcode::Constant, &[0]),
+            make(Opcode::Sub, &[]),
+            make(Opcode::Call, &[1]),
+            make(Opcode::ReturnValue, &[]),
+            // block doesn't end with expression
+            make(Opcode::Null, &[]),
+            make(Opcode::ReturnValue, &[]),
+        ];
+
+        let expected = vec![
+            make(Opcode::Closure, &[1, 0]),
+            make(Opcode::SetGlobal, &[0]),
+        ];
+
+        assert_eq!(bytecode.instructions, concat(expected));
+        assert_eq!(
+            bytecode.constants,
+            vec![Object::Integer(1), function(body, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_undefined_variable() {
+        let tests = vec![
+            ("1 + foo", "1:5: undefined variable: foo"),
+            ("let x = x;", "1:9: undefined variable: x"),
+            (
+                "function() { let a = 1; }; a",
+                "1:28: undefined variable: a",
+            ),
+            ("let f = function() { g }; f", "1:22: undefined variable: g"),
+            (
+                "let f = function() { x }; let x = x;",
+                "1:35: undefined variable: x",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parse(input);
+
+            let error = Compiler::new().compile(&program).unwrap_err();
+
+            assert_eq!(error.code(), "E0202", "input: {}", input);
+            assert_eq!(error.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_operand_overflow() {
+        // constant indexes are 2 bytes wide
+        let constants = |count: usize| (0..count).map(|i| format!("{};", i)).collect::<String>();
+
+        let bytecode = compile(&constants(65536));
+        assert_eq!(bytecode.constants.len(), 65536);
+
+        // consequence of 32763 statements ends jump at offset 65535, one more statement overflows
+        let jump =
+            |count: usize| format!("if (false) {{ {} }} else {{ 42 }}", "true; ".repeat(count));
+
+        let bytecode = compile(&jump(32763));
+        assert_eq!(bytecode.instructions.len(), 65536);
+
+        let tests = vec![
+            (
+                constants(65537),
+                "operand 65536 of OpConstant is too large, maximum is 65535",
+            ),
+            (
+                jump(32764),
+                "operand 65537 of OpJump is too large, maximum is 65535",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parse(&input);
+
+            let error = Compiler::new().compile(&program).unwrap_err();
+
+            assert_eq!(error.code(), "E0203");
+            assert_eq!(error.message(), expected);
+        }
+    }
+
+    #[test]
+    fn test_too_many() {
+        let names = |prefix: &str, count: usize| {
+            (0..count)
+                .map(|i| format!("{}{}", prefix, i))
+                .collect::<Vec<String>>()
+        };
+
+        let lets = |count: usize| {
+            let lets: String = names("a", count)
+                .iter()
+                .map(|name| format!("let {} = 1; ", name))
+                .collect();
+
+            format!("function() {{ {} a0 }}", lets)
+        };
+
+        // the last local gets index 255
+        compile(&lets(256));
+
+        let arguments = names("", 255).join(", ");
+        compile(&format!("len({})", arguments));
+
+        let free = |count: usize| {
+            let parameters = names("p", count).join(", ");
+            let sum = names("p", count).join(" + ");
+
+            format!("function({}) {{ function() {{ {} }} }}", parameters, sum)
+        };
+
+        compile(&free(255));
+
+        let tests = vec![
+            (lets(257), "too many local variables: maximum is 256"),
+            (
+                format!("len({}, 0)", arguments),
+                "too many arguments: maximum is 255",
+            ),
+            (
+                format!("function({}) {{ }}", names("p", 256).join(", ")),
+                "too many parameters: maximum is 255",
+            ),
+            (
+                format!(
+                    "function(a) {{ function({}) {{ function() {{ a + {} }} }} }}",
+                    names("p", 255).join(", "),
+                    names("p", 255).join(" + ")
+                ),
+                "too many free variables: maximum is 255",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parse(&input);
+
+            let error = Compiler::new().compile(&program).unwrap_err();
+
+            assert_eq!(error.code(), "E0204");
+            assert_eq!(error.message(), expected);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_node() {
+        let program = parse("1 + [2]");
+
+        let error = Compiler::new().compile(&program).unwrap_err();
+
+        assert_eq!(error.code(), "E0201");
+        assert_eq!(
+            error.to_string(),
+            "1:5: array literal is not supported by compiler"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::Write;
+
+use crate::object::Object;
+
+use super::{read_operands, Bytecode, Opcode, SourceMap};
+
+/// Returns human readable listing of bytecode: main program, constant pool
+/// and bodies of compiled functions. Every instruction is printed as
+///
+/// ```text
+/// 0003    2 OpConstant 1 (2.5)
+/// ```
+///
+/// where columns are offset, source line (`|` if it's the same as in previous instruction),
+/// mnemonic and operands. Constant operands are followed by the constant value
+pub fn disassemble(bytecode: &Bytecode) -> String {
+    let mut out = String::new();
+
+    out.push_str("== main ==\n");
+    disassemble_instructions(
+        &mut out,
+        &bytecode.instructions,
+        &bytecode.source_map,
+        &bytecode.constants,
+    );
+
+    if bytecode.constants.is_empty() {
+        return out;
+    }
+
+    out.push_str("\n== constants ==\n");
+
+    for (index, constant) in bytecode.constants.iter().enumerate() {
+        writeln!(
+            out,
+            "{:04} {} {}",
+            index,
+            constant.object_type(),
+            constant_value(constant)
+        )
+        .unwrap();
+    }
+
+    for (index, constant) in bytecode.constants.iter().enumerate() {
+        if let Object::CompiledFunction(function) = constant {
+            writeln!(out, "\n== function {:04} ==", index).unwrap();
+            disassemble_instructions(
+                &mut out,
+                &function.instructions,
+                &function.source_map,
+                &bytecode.constants,
+            );
+        }
+    }
+
+    out
+}
+
+fn disassemble_instructions(
+    out: &mut String,
+    instructions: &[u8],
+    source_map: &SourceMap,
+    constants: &[Object],
+) {
+    let mut offset = 0;
+    let mut previous_line = None;
+
+    while offset < instructions.len() {
+        let line = source_map
+            .binary_search_by_key(&offset, |(position, _)| *position)
+            .ok()
+            .map(|index| source_map[index].1.line);
+
+        let line_column = match line {
+            Some(_) if line == previous_line => "   |".to_string(),
+            Some(line) => format!("{:4}", line),
+            None => "   ?".to_string(),
+        };
+
+        previous_line = line;
+
+        let opcode = match Opcode::from_byte(instructions[offset]) {
+            Some(opcode) => opcode,
+            None => {
+                writeln!(
+                    out,
+                    "{:04} {} unknown opcode {}",
+                    offset, line_column, instructions[offset]
+                )
+                .unwrap();
+
+                offset += 1;
+                continue;
+            }
+        };
+
+        let (operands, read) = read_operands(opcode, &instructions[offset + 1..]);
+
+        write!(out, "{:04} {} {}", offset, line_column, opcode.name()).unwrap();
+
+        for operand in &operands {
+            write!(out, " {}", operand).unwrap();
+        }
+
+        if let (Opcode::Constant, Some(constant)) = (
+            opcode,
+            operands.first().and_then(|index| constants.get(*index)),
+        ) {
+            write!(out, " ({})", constant_value(constant)).unwrap();
+        }
+
+        out.push('\n');
+
+        offset += 1 + read;
+    }
+}
+
+/// Strings are quoted, so that their bounds are visible
+fn constant_value(constant: &Object) -> String {
+    match constant {
+        Object::String(value) => format!("{:?}", value),
+        _ => constant.inspect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{compiler::Compiler, parser::parse};
+
+    use super::disassemble;
+
+    fn compile_and_disassemble(input: &str) -> String {
+        let program = parse(input);
+
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).expect("compile failed");
+
+        disassemble(&compiler.bytecode())
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let input = "let x = 1;\nputs(x + 2.5, \"a\");";
+
+        let expected = "\
+== main ==
+0000    1 OpConstant 0 (1)
+0003    | OpSetGlobal 0
+0006    2 OpGetBuiltin 1
+0008    | OpGetGlobal 0
+0011    | OpConstant 1 (2.5)
+0014    | OpAdd
+0015    | OpConstant 2 (\"a\")
+0018    | OpCall 2
+0020    | OpPop
+
+== constants ==
+0000 INTEGER 1
+0001 FLOAT 2.5
+0002 STRING \"a\"
+";
+
+        assert_eq!(compile_and_disassemble(input), expected);
+    }
+
+    #[test]
+    fn test_disassemble_functions() {
+        let input = "let add = function(a) {\n  function(b) {\n    a + b\n  }\n};";
+
+        let expected = "\
+== main ==
+0000    1 OpClosure 1 0
+0004    | OpSetGlobal 0
+
+== constants ==
+0000 COMPILED_FUNCTION compiled function(1 parameters)
+0001 COMPILED_FUNCTION compiled function(1 parameters)
+
+== function 0000 ==
+0000    3 OpGetFree 0
+0002    | OpGetLocal 0
+0004    | OpAdd
+0005    2 OpReturnValue
+
+== function 0001 ==
+0000    2 OpGetLocal 0
+0002    | OpClosure 0 1
+0006    1 OpReturnValue
+";
+
+        assert_eq!(compile_and_disassemble(input), expected);
+    }
+
+    #[test]
+    fn test_disassemble_empty_program() {
+        assert_eq!(compile_and_disassemble(""), "== main ==\n");
+    }
+}
@@ -0,0 +1,77 @@
+use std::{error::Error, fmt};
+
+use crate::token::Span;
+
+/// Error found while compiling program to bytecode
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompileError {
+    /// Node can't be compiled by bytecode backend yet: "function(x) { x }"
+    UnsupportedNode { node: &'static str, span: Span },
+    /// Identifier isn't bound in any enclosing scope: "let a = b;"
+    UndefinedVariable { name: String, span: Span },
+    /// Operand doesn't fit into instruction: 65536th constant or jump past 65535 bytes
+    OperandOverflow {
+        opcode: &'static str,
+        operand: usize,
+        max: usize,
+        span: Span,
+    },
+    /// Function exceeds limit of 1 byte wide operand: 257 local variables, 256 arguments
+    TooMany {
+        what: &'static str,
+        max: usize,
+        span: Span,
+    },
+}
+
+impl CompileError {
+    /// Returns stable error code. Codes are never reused for other errors
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompileError::UnsupportedNode { .. } => "E0201",
+            CompileError::UndefinedVariable { .. } => "E0202",
+            CompileError::OperandOverflow { .. } => "E0203",
+            CompileError::TooMany { .. } => "E0204",
+        }
+    }
+
+    /// Returns location of the node which caused error
+    pub fn span(&self) -> Span {
+        match self {
+            CompileError::UnsupportedNode { span, .. } => *span,
+            CompileError::UndefinedVariable { span, .. } => *span,
+            CompileError::OperandOverflow { span, .. } => *span,
+            CompileError::TooMany { span, .. } => *span,
+        }
+    }
+
+    /// Returns error description without location
+    pub fn message(&self) -> String {
+        match self {
+            CompileError::UnsupportedNode { node, .. } => {
+                format!("{} is not supported by compiler", node)
+            }
+            CompileError::UndefinedVariable { name, .. } => format!("undefined variable: {}", name),
+            CompileError::OperandOverflow {
+                opcode,
+                operand,
+                max,
+                ..
+            } => format!(
+                "operand {} of {} is too large, maximum is {}",
+                operand, opcode, max
+            ),
+            CompileError::TooMany { what, max, .. } => {
+                format!("too many {}: maximum is {}", what, max)
+            }
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span(), self.message())
+    }
+}
+
+impl Error for CompileError {}
@@ -0,0 +1,11 @@
+pub mod code;
+pub mod compiler;
+pub mod disassembler;
+pub mod error;
+pub mod symbol_table;
+
+pub use self::code::*;
+pub use self::compiler::*;
+pub use self::disassembler::*;
+pub use self::error::*;
+pub use self::symbol_table::*;
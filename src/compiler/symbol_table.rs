@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+/// Storage a name is resolved to. Determines which opcode loads the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolScope {
+    Global,   // top level let statements
+    Local,    // parameters and let statements inside function
+    Builtin,  // native functions: len, puts
+    Free,     // local of enclosing function captured by closure
+    Function, // name of the function being defined, used for recursion
+}
+
+/// Name resolved to storage slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub scope: SymbolScope,
+    pub index: usize,
+}
+
+/// Maps names to symbols. Every function body gets its own table enclosed by the outer one
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    outer: Option<Box<SymbolTable>>,
+    store: HashMap<String, Symbol>,
+    num_definitions: usize,
+    free_symbols: Vec<Symbol>, // original symbols of captured variables, in capture order
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    /// Creates table for function body
+    pub fn new_enclosed(outer: SymbolTable) -> Self {
+        SymbolTable {
+            outer: Some(Box::new(outer)),
+            ..Default::default()
+        }
+    }
+
+    /// Binds name to the next slot. Table without outer one defines globals.
+    /// Redefined name keeps its slot, so closures which already refer to it see the new value
+    pub fn define(&mut self, name: &str) -> Symbol {
+        let scope = match self.outer {
+            Some(_) => SymbolScope::Local,
+            None => SymbolScope::Global,
+        };
+
+        if let Some(symbol) = self.store.get(name).filter(|symbol| symbol.scope == scope) {
+            return symbol.clone();
+        }
+
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope,
+            index: self.num_definitions,
+        };
+
+        self.num_definitions += 1;
+        self.store.insert(name.to_string(), symbol.clone());
+
+        symbol
+    }
+
+    /// Binds name in the outermost table. Used for globals which function body
+    /// refers to before their let statement
+    pub fn define_global(&mut self, name: &str) -> Symbol {
+        match self.outer.as_mut() {
+            Some(outer) => outer.define_global(name),
+            None => self.define(name),
+        }
+    }
+
+    /// Binds name to builtin function with index from builtins registry
+    pub fn define_builtin(&mut self, index: usize, name: &str) -> Symbol {
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope: SymbolScope::Builtin,
+            index,
+        };
+
+        self.store.insert(name.to_string(), symbol.clone());
+
+        symbol
+    }
+
+    /// Binds name of the function which owns this table, so that it can call itself
+    pub fn define_function_name(&mut self, name: &str) -> Symbol {
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope: SymbolScope::Function,
+            index: 0,
+        };
+
+        self.store.insert(name.to_string(), symbol.clone());
+
+        symbol
+    }
+
+    /// Looks up name in this and enclosing tables. Locals of enclosing functions
+    /// are captured as free variables of every table in between
+    pub fn resolve(&mut self, name: &str) -> Option<Symbol> {
+        if let Some(symbol) = self.store.get(name) {
+            return Some(symbol.clone());
+        }
+
+        let symbol = self.outer.as_mut()?.resolve(name)?;
+
+        match symbol.scope {
+            SymbolScope::Global | SymbolScope::Builtin => Some(symbol),
+            _ => Some(self.define_free(symbol)),
+        }
+    }
+
+    /// Detaches enclosing table. Used when function body is compiled
+    pub fn take_outer(&mut self) -> Option<SymbolTable> {
+        self.outer.take().map(|outer| *outer)
+    }
+
+    /// Returns number of slots needed for locals
+    pub fn num_definitions(&self) -> usize {
+        self.num_definitions
+    }
+
+    /// Returns symbols captured from enclosing function, resolved in its table
+    pub fn free_symbols(&self) -> &[Symbol] {
+        &self.free_symbols
+    }
+
+    fn define_free(&mut self, original: Symbol) -> Symbol {
+        let symbol = Symbol {
+            name: original.name.clone(),
+            scope: SymbolScope::Free,
+            index: self.free_symbols.len(),
+        };
+
+        self.free_symbols.push(original);
+        self.store.insert(symbol.name.clone(), symbol.clone());
+
+        symbol
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Symbol, SymbolScope, SymbolTable};
+
+    fn symbol(name: &str, scope: SymbolScope, index: usize) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            scope,
+            index,
+        }
+    }
+
+    #[test]
+    fn test_define_and_resolve() {
+        let mut global = SymbolTable::new();
+        assert_eq!(global.define("a"), symbol("a", SymbolScope::Global, 0));
+        assert_eq!(global.define("b"), symbol("b", SymbolScope::Global, 1));
+
+        let mut local = SymbolTable::new_enclosed(global);
+        assert_eq!(local.define("c"), symbol("c", SymbolScope::Local, 0));
+        assert_eq!(local.define("d"), symbol("d", SymbolScope::Local, 1));
+
+        let tests = vec![
+            ("a", symbol("a", SymbolScope::Global, 0)),
+            ("b", symbol("b", SymbolScope::Global, 1)),
+            ("c", symbol("c", SymbolScope::Local, 0)),
+            ("d", symbol("d", SymbolScope::Local, 1)),
+        ];
+
+        for (name, expected) in tests {
+            assert_eq!(local.resolve(name), Some(expected), "name: {}", name);
+        }
+
+        assert_eq!(local.num_definitions(), 2);
+        assert!(local.free_symbols().is_empty());
+        assert_eq!(local.resolve("e"), None);
+    }
+
+    #[test]
+    fn test_redefine() {
+        let mut global = SymbolTable::new();
+        global.define("a");
+        global.define("b");
+        assert_eq!(global.define("a"), symbol("a", SymbolScope::Global, 0));
+
+        let mut local = SymbolTable::new_enclosed(global);
+        local.define_function_name("f");
+        assert_eq!(
+            local.resolve("b"),
+            Some(symbol("b", SymbolScope::Global, 1))
+        );
+
+        // outer and function name bindings are shadowed by a new slot
+        assert_eq!(local.define("b"), symbol("b", SymbolScope::Local, 0));
+        assert_eq!(local.define("f"), symbol("f", SymbolScope::Local, 1));
+        assert_eq!(local.define("b"), symbol("b", SymbolScope::Local, 0));
+        assert_eq!(local.num_definitions(), 2);
+    }
+
+    #[test]
+    fn test_builtins() {
+        let mut global = SymbolTable::new();
+        global.define_builtin(0, "len");
+        global.define_builtin(1, "puts");
+
+        let mut local = SymbolTable::new_enclosed(SymbolTable::new_enclosed(global));
+
+        assert_eq!(
+            local.resolve("puts"),
+            Some(symbol("puts", SymbolScope::Builtin, 1))
+        );
+        assert!(local.free_symbols().is_empty());
+    }
+
+    #[test]
+    fn test_free_variables() {
+        let mut global = SymbolTable::new();
+        global.define("a");
+
+        let mut first = SymbolTable::new_enclosed(global);
+        first.define("b");
+
+        let mut second = SymbolTable::new_enclosed(first);
+        second.define("c");
+
+        let tests = vec![
+            ("a", symbol("a", SymbolScope::Global, 0)),
+            ("b", symbol("b", SymbolScope::Free, 0)),
+            ("c", symbol("c", SymbolScope::Local, 0)),
+        ];
+
+        for (name, expected) in tests {
+            assert_eq!(second.resolve(name), Some(expected), "name: {}", name);
+        }
+
+        assert_eq!(second.free_symbols(), &[symbol("b", SymbolScope::Local, 0)]);
+
+        // variable of the outermost function is captured by every function in between
+        let mut third = SymbolTable::new_enclosed(second);
+        assert_eq!(third.resolve("b"), Some(symbol("b", SymbolScope::Free, 0)));
+        assert_eq!(third.free_symbols(), &[symbol("b", SymbolScope::Free, 0)]);
+        assert_eq!(third.resolve("x"), None);
+    }
+
+    #[test]
+    fn test_define_global() {
+        let mut global = SymbolTable::new();
+        global.define("a");
+
+        let mut local = SymbolTable::new_enclosed(SymbolTable::new_enclosed(global));
+        assert_eq!(
+            local.define_global("b"),
+            symbol("b", SymbolScope::Global, 1)
+        );
+        assert_eq!(
+            local.resolve("b"),
+            Some(symbol("b", SymbolScope::Global, 1))
+        );
+        assert_eq!(local.num_definitions(), 0);
+
+        // later top level let statement keeps the slot
+        let mut global = local.take_outer().unwrap().take_outer().unwrap();
+        assert_eq!(global.define("b"), symbol("b", SymbolScope::Global, 1));
+    }
+
+    #[test]
+    fn test_function_name() {
+        let mut local = SymbolTable::new_enclosed(SymbolTable::new());
+        local.define_function_name("f");
+
+        assert_eq!(
+            local.resolve("f"),
+            Some(symbol("f", SymbolScope::Function, 0))
+        );
+
+        // parameter with the same name shadows function
+        local.define("f");
+        assert_eq!(local.resolve("f"), Some(symbol("f", SymbolScope::Local, 0)));
+    }
+}
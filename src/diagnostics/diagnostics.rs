@@ -0,0 +1,294 @@
+use crate::{
+    compiler::CompileError,
+    lexer::LexError,
+    object::RuntimeError,
+    parser::ParseError,
+    token::{Kind, Span},
+};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Error report which can be rendered with source snippet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<String>, // help notes printed below snippet
+}
+
+impl Diagnostic {
+    pub fn error(message: String) -> Self {
+        Diagnostic {
+            code: None,
+            message,
+            span: None,
+            notes: vec![],
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        // errors of illegal tokens come from lexer and keep its notes
+        if let ParseError::Lex(error) = error {
+            return Diagnostic::from(error);
+        }
+
+        let diagnostic = Diagnostic::error(error.message())
+            .with_code(error.code())
+            .with_span(error.span());
+
+        match error {
+            ParseError::UnexpectedToken {
+                expected: Kind::Semicolon,
+                ..
+            } => diagnostic.with_note("statements must be separated with `;`"),
+            ParseError::UnterminatedBlock { .. } => {
+                diagnostic.with_note("add `}` to close the block")
+            }
+            ParseError::InvalidInteger { .. } => diagnostic.with_note(&format!(
+                "integers must be between {} and {}",
+                i64::MIN,
+                i64::MAX
+            )),
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<&LexError> for Diagnostic {
+    fn from(error: &LexError) -> Self {
+        let diagnostic = Diagnostic::error(error.message())
+            .with_code(error.code())
+            .with_span(error.span());
+
+        match error {
+            LexError::InvalidEscape { .. } => {
+                diagnostic.with_note("supported escapes are \\n, \\t, \\r, \\\", \\\\ and \\u{...}")
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<&CompileError> for Diagnostic {
+    fn from(error: &CompileError) -> Self {
+        Diagnostic::error(error.message())
+            .with_code(error.code())
+            .with_span(error.span())
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        let diagnostic = Diagnostic::error(error.message.clone());
+
+        match error.span {
+            Some(span) => diagnostic.with_span(span),
+            None => diagnostic,
+        }
+    }
+}
+
+/// Renders diagnostics in compiler style:
+///
+/// ```text
+/// error[E0001]: expected next token: Semicolon. Got token: Let
+///  --> main.mk:1:11
+///   |
+/// 1 | let x = 5 let y = 10;
+///   |           ^^^
+///   = help: statements must be separated with `;`
+/// ```
+pub struct Renderer<'a> {
+    file_name: &'a str,
+    source: &'a str,
+    color: bool, // use ANSI colors. Should be enabled only for terminals
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(file_name: &'a str, source: &'a str, color: bool) -> Self {
+        Renderer {
+            file_name,
+            source,
+            color,
+        }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut sb = String::new();
+
+        sb.push_str(&self.paint(RED, "error"));
+
+        if let Some(code) = diagnostic.code {
+            sb.push_str(&self.paint(RED, &format!("[{}]", code)));
+        }
+
+        sb.push_str(&self.paint(BOLD, &format!(": {}", diagnostic.message)));
+        sb.push('\n');
+
+        let span = match diagnostic.span {
+            Some(span) => span,
+            None => {
+                self.render_notes(&mut sb, &diagnostic.notes, 0);
+                return sb;
+            }
+        };
+
+        let line_number = span.line.to_string();
+        let padding = " ".repeat(line_number.len());
+
+        sb.push_str(&format!(
+            "{}{} {}:{}\n",
+            padding,
+            self.paint(BLUE, "-->"),
+            self.file_name,
+            span
+        ));
+
+        let line = self.source.lines().nth(span.line.saturating_sub(1));
+        let line = line.unwrap_or("");
+
+        sb.push_str(&format!("{} {}\n", padding, self.paint(BLUE, "|")));
+        sb.push_str(&format!(
+            "{} {} {}\n",
+            self.paint(BLUE, &line_number),
+            self.paint(BLUE, "|"),
+            line
+        ));
+
+        // underline until the end of span, but not further than the end of line
+        let line_length = line.chars().count();
+        let start = span.column.saturating_sub(1).min(line_length);
+        let width = self.source[span.start.min(self.source.len())..span.end.min(self.source.len())]
+            .chars()
+            .take_while(|ch| *ch != '\n')
+            .count()
+            .max(1);
+
+        sb.push_str(&format!(
+            "{} {} {}{}\n",
+            padding,
+            self.paint(BLUE, "|"),
+            " ".repeat(start),
+            self.paint(RED, &"^".repeat(width))
+        ));
+
+        self.render_notes(&mut sb, &diagnostic.notes, padding.len());
+
+        sb
+    }
+
+    fn render_notes(&self, sb: &mut String, notes: &[String], padding: usize) {
+        for note in notes {
+            sb.push_str(&format!(
+                "{} {} {}: {}\n",
+                " ".repeat(padding),
+                self.paint(BLUE, "="),
+                self.paint(CYAN, "help"),
+                note
+            ));
+        }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{lexer::Lexer, parser::Parser};
+
+    use super::{Diagnostic, Renderer};
+
+    fn render_parse_errors(input: &str, color: bool) -> String {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        let renderer = Renderer::new("main.mk", input, color);
+
+        parser
+            .errors()
+            .iter()
+            .map(|error| renderer.render(&Diagnostic::from(error)))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_parse_error() {
+        let input = "let a = 1;\nlet x = 5 let y = 10;\n";
+
+        let expected = "\
+error[E0001]: expected next token: Semicolon. Got token: Let
+ --> main.mk:2:11
+  |
+2 | let x = 5 let y = 10;
+  |           ^^^
+  = help: statements must be separated with `;`
+";
+
+        assert_eq!(render_parse_errors(input, false), expected);
+    }
+
+    #[test]
+    fn test_render_error_at_end_of_input() {
+        let input = "if (x) { x";
+
+        let expected = "\
+error[E0005]: unterminated block: expected Rbrace. Got token: Eof
+ --> main.mk:1:11
+  |
+1 | if (x) { x
+  |           ^
+  = help: add `}` to close the block
+";
+
+        assert_eq!(render_parse_errors(input, false), expected);
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let diagnostic = Diagnostic::error("something failed".to_string()).with_note("try again");
+
+        let renderer = Renderer::new("main.mk", "", false);
+
+        assert_eq!(
+            renderer.render(&diagnostic),
+            "error: something failed\n = help: try again\n"
+        );
+    }
+
+    #[test]
+    fn test_render_with_color() {
+        let output = render_parse_errors("let x = ;", true);
+
+        assert!(output.starts_with("\x1b[1;31merror\x1b[0m\x1b[1;31m[E0002]\x1b[0m"));
+        assert!(output.contains("\x1b[1;31m^\x1b[0m"));
+    }
+}
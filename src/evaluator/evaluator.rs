@@ -0,0 +1,1102 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    ast::{
+        ast::{Node, Program},
+        BlockStatement, CallExpression, Expression, HashLiteral, IfExpression, IndexExpression,
+        InfixExpression, Statement,
+    },
+    object::{get_builtin, Environment, Function, HashPair, Object, RuntimeError},
+};
+
+/// Maximum number of nested function calls, same as frame limit of the virtual machine.
+/// Every call takes several native frames, so the limit is kept low enough
+/// for the default 2MB thread stack in debug builds
+pub(crate) const MAX_CALL_DEPTH: usize = 128;
+
+/// Evaluates program and returns value of the last statement
+pub fn eval(program: &Program, env: &Rc<RefCell<Environment>>) -> Object {
+    let mut result = Object::Null;
+
+    for statement in &program.statements {
+        result = eval_statement(statement, env);
+
+        match result {
+            Object::ReturnValue(value) => return *value,
+            Object::Error(_) => return result,
+            _ => {}
+        }
+    }
+
+    result
+}
+
+fn eval_statement(statement: &Statement, env: &Rc<RefCell<Environment>>) -> Object {
+    match statement {
+        Statement::ExpressionStatement(stmt) => match &stmt.expression {
+            Some(expr) => eval_expression(expr, env),
+            None => Object::Null,
+        },
+        Statement::ReturnStatement(stmt) => {
+            let value = match &stmt.return_value {
+                Some(expr) => eval_expression(expr, env),
+                None => Object::Null,
+            };
+
+            if value.is_error() {
+                return value;
+            }
+
+            Object::ReturnValue(Box::new(value))
+        }
+        Statement::LetStatement(stmt) => {
+            let value = match &stmt.value {
+                Some(expr) => eval_expression(expr, env),
+                None => Object::Null,
+            };
+
+            if value.is_error() {
+                return value;
+            }
+
+            env.borrow_mut().set(&stmt.name.value, value);
+
+            Object::Null
+        }
+        Statement::Error(stmt) => Object::Error(RuntimeError {
+            message: String::from("invalid statement"),
+            span: Some(stmt.span),
+        }),
+    }
+}
+
+/// Evaluates expression. New errors get location of the expression which caused them
+fn eval_expression(expression: &Expression, env: &Rc<RefCell<Environment>>) -> Object {
+    match eval_expression_node(expression, env) {
+        Object::Error(mut error) if error.span.is_none() => {
+            error.span = Some(expression.span());
+            Object::Error(error)
+        }
+        result => result,
+    }
+}
+
+fn eval_expression_node(expression: &Expression, env: &Rc<RefCell<Environment>>) -> Object {
+    match expression {
+        Expression::IntegerLiteral(literal) => Object::Integer(literal.value),
+        Expression::FloatLiteral(literal) => Object::Float(literal.value),
+        Expression::Boolean(literal) => native_bool_to_object(literal.value),
+        Expression::StringLiteral(literal) => Object::String(literal.value.clone()),
+        Expression::Identifier(ident) => eval_identifier(&ident.value, env),
+        Expression::PrefixExpression(expr) => {
+            let right = match expr.right.as_deref() {
+                Some(right) => eval_expression(right, env),
+                None => Object::Null,
+            };
+
+            if right.is_error() {
+                return right;
+            }
+
+            eval_prefix_expression(&expr.operator, right)
+        }
+        Expression::InfixExpression(expr) if matches!(expr.operator.as_str(), "&&" | "||") => {
+            eval_logical_expression(expr, env)
+        }
+        Expression::InfixExpression(expr) => {
+            let left = match expr.left.as_deref() {
+                Some(left) => eval_expression(left, env),
+                None => Object::Null,
+            };
+
+            if left.is_error() {
+                return left;
+            }
+
+            let right = match expr.right.as_deref() {
+                Some(right) => eval_expression(right, env),
+                None => Object::Null,
+            };
+
+            if right.is_error() {
+                return right;
+            }
+
+            eval_infix_expression(&expr.operator, left, right)
+        }
+        Expression::IfExpression(expr) => eval_if_expression(expr, env),
+        Expression::FunctionLiteral(literal) => Object::Function(Rc::new(Function {
+            parameters: literal.parameters.clone(),
+            body: literal.body.clone(),
+            env: Rc::clone(env),
+        })),
+        Expression::CallExpression(expr) => eval_call_expression(expr, env),
+        Expression::ArrayLiteral(literal) => match eval_expressions(&literal.elements, env) {
+            Ok(elements) => Object::Array(elements),
+            Err(error) => error,
+        },
+        Expression::IndexExpression(expr) => eval_index_expression(expr, env),
+        Expression::HashLiteral(literal) => eval_hash_literal(literal, env),
+        Expression::Error(_) => new_error(String::from("invalid expression")),
+    }
+}
+
+/// Evaluates statements of the block.
+/// Return values are not unwrapped so that they can stop evaluation of outer blocks
+fn eval_block_statement(block: &BlockStatement, env: &Rc<RefCell<Environment>>) -> Object {
+    let mut result = Object::Null;
+
+    for statement in &block.statements {
+        result = eval_statement(statement, env);
+
+        if matches!(result, Object::ReturnValue(_) | Object::Error(_)) {
+            return result;
+        }
+    }
+
+    result
+}
+
+fn eval_if_expression(expr: &IfExpression, env: &Rc<RefCell<Environment>>) -> Object {
+    let condition = eval_expression(&expr.condition, env);
+
+    if condition.is_error() {
+        return condition;
+    }
+
+    if condition.is_truthy() {
+        eval_block_statement(&expr.consequence, env)
+    } else if let Some(alternative) = &expr.alternative {
+        eval_block_statement(alternative, env)
+    } else {
+        Object::Null
+    }
+}
+
+fn eval_call_expression(expr: &CallExpression, env: &Rc<RefCell<Environment>>) -> Object {
+    let function = eval_expression(&expr.function, env);
+
+    if function.is_error() {
+        return function;
+    }
+
+    match eval_expressions(&expr.arguments, env) {
+        Ok(arguments) => apply_function(function, arguments, env),
+        Err(error) => error,
+    }
+}
+
+/// Evaluates expressions from left to right. Stops at the first error
+fn eval_expressions(
+    expressions: &[Expression],
+    env: &Rc<RefCell<Environment>>,
+) -> Result<Vec<Object>, Object> {
+    let mut result = Vec::with_capacity(expressions.len());
+
+    for expression in expressions {
+        let value = eval_expression(expression, env);
+
+        if value.is_error() {
+            return Err(value);
+        }
+
+        result.push(value);
+    }
+
+    Ok(result)
+}
+
+fn eval_index_expression(expr: &IndexExpression, env: &Rc<RefCell<Environment>>) -> Object {
+    let left = eval_expression(&expr.left, env);
+
+    if left.is_error() {
+        return left;
+    }
+
+    let index = eval_expression(&expr.index, env);
+
+    if index.is_error() {
+        return index;
+    }
+
+    match (&left, &index) {
+        (Object::Array(elements), Object::Integer(index)) => eval_array_index(elements, *index),
+        (Object::Hash(pairs), _) => match index.hash_key() {
+            Some(key) => pairs
+                .get(&key)
+                .map(|pair| pair.value.clone())
+                .unwrap_or(Object::Null),
+            None => new_error(format!("unusable as hash key: {}", index.object_type())),
+        },
+        _ => new_error(format!(
+            "index operator not supported: {}[{}]",
+            left.object_type(),
+            index.object_type()
+        )),
+    }
+}
+
+fn eval_hash_literal(literal: &HashLiteral, env: &Rc<RefCell<Environment>>) -> Object {
+    let mut pairs = HashMap::with_capacity(literal.pairs.len());
+
+    for (key_expr, value_expr) in &literal.pairs {
+        let key = eval_expression(key_expr, env);
+
+        if key.is_error() {
+            return key;
+        }
+
+        let hash_key = match key.hash_key() {
+            Some(hash_key) => hash_key,
+            None => return new_error(format!("unusable as hash key: {}", key.object_type())),
+        };
+
+        let value = eval_expression(value_expr, env);
+
+        if value.is_error() {
+            return value;
+        }
+
+        pairs.insert(hash_key, HashPair { key, value });
+    }
+
+    Object::Hash(pairs)
+}
+
+/// Returns element at index. Indexes are zero based and counted from the start only:
+/// negative and out of bounds indexes evaluate to null instead of error
+fn eval_array_index(elements: &[Object], index: i64) -> Object {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| elements.get(index))
+        .cloned()
+        .unwrap_or(Object::Null)
+}
+
+/// Looks up identifier in environment first, so that builtins can be shadowed
+fn eval_identifier(name: &str, env: &Rc<RefCell<Environment>>) -> Object {
+    if let Some(value) = env.borrow().get(name) {
+        return value;
+    }
+
+    match get_builtin(name) {
+        Some(builtin) => builtin,
+        None => new_error(format!("identifier not found: {}", name)),
+    }
+}
+
+/// Calls function in a new environment which extends the captured one.
+/// Builtins are called directly and write to output of the caller environment
+fn apply_function(
+    function: Object,
+    arguments: Vec<Object>,
+    env: &Rc<RefCell<Environment>>,
+) -> Object {
+    let function = match function {
+        Object::Function(function) => function,
+        Object::Builtin(builtin) => {
+            let output = env.borrow().output();
+            let mut output = output.borrow_mut();
+
+            return builtin.call(&arguments, &mut *output);
+        }
+        _ => return new_error(format!("not a function: {}", function.object_type())),
+    };
+
+    if function.parameters.len() != arguments.len() {
+        return new_error(format!(
+            "wrong number of arguments: want={}, got={}",
+            function.parameters.len(),
+            arguments.len()
+        ));
+    }
+
+    let call_depth = env.borrow().call_depth() + 1;
+
+    if call_depth > MAX_CALL_DEPTH {
+        return new_error("stack overflow".to_string());
+    }
+
+    let mut extended_env = Environment::new_enclosed(Rc::clone(&function.env), call_depth);
+
+    for (parameter, argument) in function.parameters.iter().zip(arguments) {
+        extended_env.set(&parameter.value, argument);
+    }
+
+    let extended_env = Rc::new(RefCell::new(extended_env));
+
+    match eval_block_statement(&function.body, &extended_env) {
+        Object::ReturnValue(value) => *value,
+        result => result,
+    }
+}
+
+/// Evaluates prefix expressions: !true, -5. Shared with the virtual machine
+pub(crate) fn eval_prefix_expression(operator: &str, right: Object) -> Object {
+    match operator {
+        "!" => native_bool_to_object(!right.is_truthy()),
+        "-" => match right {
+            Object::Integer(value) => Object::Integer(value.wrapping_neg()),
+            Object::Float(value) => Object::Float(-value),
+            _ => new_error(format!("unknown operator: -{}", right.object_type())),
+        },
+        _ => new_error(format!(
+            "unknown operator: {}{}",
+            operator,
+            right.object_type()
+        )),
+    }
+}
+
+/// Evaluates infix expressions: 5 + 5, true == false. Shared with the virtual machine
+pub(crate) fn eval_infix_expression(operator: &str, left: Object, right: Object) -> Object {
+    match (&left, &right) {
+        (Object::Integer(l), Object::Integer(r)) => eval_integer_infix_expression(operator, *l, *r),
+        // integer is promoted to float if other operand is float
+        (Object::Float(l), Object::Float(r)) => eval_float_infix_expression(operator, *l, *r),
+        (Object::Integer(l), Object::Float(r)) => {
+            eval_float_infix_expression(operator, *l as f64, *r)
+        }
+        (Object::Float(l), Object::Integer(r)) => {
+            eval_float_infix_expression(operator, *l, *r as f64)
+        }
+        (Object::String(l), Object::String(r)) => match operator {
+            "+" => Object::String(format!("{}{}", l, r)),
+            "==" => native_bool_to_object(l == r),
+            "!=" => native_bool_to_object(l != r),
+            _ => new_error(format!(
+                "unknown operator: {} {} {}",
+                left.object_type(),
+                operator,
+                right.object_type()
+            )),
+        },
+        (Object::Boolean(l), Object::Boolean(r)) => match operator {
+            "==" => native_bool_to_object(l == r),
+            "!=" => native_bool_to_object(l != r),
+            _ => new_error(format!(
+                "unknown operator: {} {} {}",
+                left.object_type(),
+                operator,
+                right.object_type()
+            )),
+        },
+        _ if left.object_type() != right.object_type() => new_error(format!(
+            "type mismatch: {} {} {}",
+            left.object_type(),
+            operator,
+            right.object_type()
+        )),
+        _ => new_error(format!(
+            "unknown operator: {} {} {}",
+            left.object_type(),
+            operator,
+            right.object_type()
+        )),
+    }
+}
+
+/// Evaluates "&&" and "||". Right side is evaluated only if left one doesn't decide the result
+fn eval_logical_expression(expr: &InfixExpression, env: &Rc<RefCell<Environment>>) -> Object {
+    let left = match expr.left.as_deref() {
+        Some(left) => eval_expression(left, env),
+        None => Object::Null,
+    };
+
+    if left.is_error() {
+        return left;
+    }
+
+    match (expr.operator.as_str(), left.is_truthy()) {
+        ("&&", false) => return Object::FALSE,
+        ("||", true) => return Object::TRUE,
+        _ => {}
+    }
+
+    let right = match expr.right.as_deref() {
+        Some(right) => eval_expression(right, env),
+        None => Object::Null,
+    };
+
+    if right.is_error() {
+        return right;
+    }
+
+    native_bool_to_object(right.is_truthy())
+}
+
+fn eval_integer_infix_expression(operator: &str, left: i64, right: i64) -> Object {
+    match operator {
+        "+" => Object::Integer(left.wrapping_add(right)),
+        "-" => Object::Integer(left.wrapping_sub(right)),
+        "*" => Object::Integer(left.wrapping_mul(right)),
+        "/" => {
+            if right == 0 {
+                return new_error("division by zero".to_string());
+            }
+
+            Object::Integer(left.wrapping_div(right))
+        }
+        "%" => {
+            if right == 0 {
+                return new_error("division by zero".to_string());
+            }
+
+            Object::Integer(left.wrapping_rem(right))
+        }
+        "**" => {
+            if right < 0 {
+                return new_error(format!("negative exponent: {}", right));
+            }
+
+            Object::Integer(wrapping_pow(left, right))
+        }
+        "<" => native_bool_to_object(left < right),
+        ">" => native_bool_to_object(left > right),
+        "<=" => native_bool_to_object(left <= right),
+        ">=" => native_bool_to_object(left >= right),
+        "==" => native_bool_to_object(left == right),
+        "!=" => native_bool_to_object(left != right),
+        _ => new_error(format!("unknown operator: INTEGER {} INTEGER", operator)),
+    }
+}
+
+/// Evaluates float arithmetic. Follows IEEE 754, so division by zero gives infinity
+fn eval_float_infix_expression(operator: &str, left: f64, right: f64) -> Object {
+    match operator {
+        "+" => Object::Float(left + right),
+        "-" => Object::Float(left - right),
+        "*" => Object::Float(left * right),
+        "/" => Object::Float(left / right),
+        "%" => Object::Float(left % right),
+        "**" => Object::Float(left.powf(right)),
+        "<" => native_bool_to_object(left < right),
+        ">" => native_bool_to_object(left > right),
+        "<=" => native_bool_to_object(left <= right),
+        ">=" => native_bool_to_object(left >= right),
+        "==" => native_bool_to_object(left == right),
+        "!=" => native_bool_to_object(left != right),
+        _ => new_error(format!("unknown operator: FLOAT {} FLOAT", operator)),
+    }
+}
+
+/// Raises base to non-negative power. Wraps on overflow like other integer operators
+fn wrapping_pow(mut base: i64, mut exponent: i64) -> i64 {
+    let mut result: i64 = 1;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result.wrapping_mul(base);
+        }
+
+        base = base.wrapping_mul(base);
+        exponent >>= 1;
+    }
+
+    result
+}
+
+fn native_bool_to_object(value: bool) -> Object {
+    if value {
+        Object::TRUE
+    } else {
+        Object::FALSE
+    }
+}
+
+fn new_error(message: String) -> Object {
+    Object::Error(RuntimeError::new(message))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, io::Write, rc::Rc};
+
+    use crate::{
+        object::{Environment, HashKey, Object},
+        parser::parse,
+    };
+
+    use super::eval;
+
+    fn test_eval(input: &str) -> Object {
+        let program = parse(input);
+
+        let env = Rc::new(RefCell::new(Environment::new()));
+
+        eval(&program, &env)
+    }
+
+    /// Evaluates input and returns result with everything program printed
+    fn test_eval_with_output(input: &str) -> (Object, String) {
+        let program = parse(input);
+
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let sink: Rc<RefCell<dyn Write>> = output.clone();
+        let env = Rc::new(RefCell::new(Environment::with_output(sink)));
+
+        let result = eval(&program, &env);
+        let output = String::from_utf8(output.borrow().clone()).unwrap();
+
+        (result, output)
+    }
+
+    #[test]
+    fn test_eval_integer_expression() {
+        let tests = vec![
+            ("5", 5),
+            ("10", 10),
+            ("-5", -5),
+            ("--10", 10),
+            ("5 + 5 + 5 + 5 - 10", 10),
+            ("2 * 2 * 2 * 2 * 2", 32),
+            ("-50 + 100 + -50", 0),
+            ("5 * 2 + 10", 20),
+            ("5 + 2 * 10", 25),
+            ("50 / 2 * 2 + 10", 60),
+            ("3 * 3 * 3 + 10", 37),
+            ("0xff + 0b1 + 0o10", 264),
+            ("1_000 * 3", 3000),
+            ("10 % 3", 1),
+            ("-7 % 3", -1),
+            ("2 + 10 % 4 * 3", 8),
+            ("2 ** 10", 1024),
+            ("2 ** 3 ** 2", 512),
+            ("-2 ** 2", -4),
+            ("3 * 2 ** 2", 12),
+            ("5 ** 0", 1),
+            ("2 ** 64", 0),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Integer(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_boolean_expression() {
+        let tests = vec![
+            ("1 < 2", true),
+            ("1 > 2", false),
+            ("1 < 1", false),
+            ("1 == 1", true),
+            ("1 != 1", false),
+            ("1 == 2", false),
+            ("1 != 2", true),
+            ("1 < 2 == 2 > 1", true),
+            ("1 < 2 == 2 < 1", false),
+            ("true", true),
+            ("false", false),
+            ("true == true", true),
+            ("true != false", true),
+            ("(1 < 2) == true", true),
+            ("(1 > 2) == true", false),
+            ("1 <= 2", true),
+            ("2 <= 2", true),
+            ("3 <= 2", false),
+            ("1 >= 2", false),
+            ("2 >= 2", true),
+            ("true && true", true),
+            ("true && false", false),
+            ("false || true", true),
+            ("false || false", false),
+            ("1 < 2 && 2 < 3", true),
+            ("1 > 2 || 2 > 3", false),
+            ("1 && 0", true),
+            ("2 ** 3 ** 2 == 2 ** 9", true),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Boolean(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_float_expression() {
+        let tests = vec![
+            ("3.5", 3.5),
+            ("-2.5", -2.5),
+            ("1e3", 1000.0),
+            ("1_000.5", 1000.5),
+            ("0.1 + 0.2 * 2", 0.5),
+            ("1 + 0.5", 1.5),
+            ("0.5 + 1", 1.5),
+            ("7 / 2.0", 3.5),
+            ("1 / 0.0", f64::INFINITY),
+            ("7.5 % 2", 1.5),
+            ("2 ** 0.5 ** 2", 2f64.powf(0.25)),
+            ("4.0 ** -1", 0.25),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Float(expected),
+                "input: {}",
+                input
+            );
+        }
+
+        let tests = vec![
+            ("1 == 1.0", true),
+            ("1.5 > 1", true),
+            ("2 <= 1.5", false),
+            ("0.1 + 0.2 == 0.3", false),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Boolean(expected),
+                "input: {}",
+                input
+            );
+        }
+
+        assert_eq!(test_eval("2.0").inspect(), "2.0");
+        assert_eq!(test_eval("1e-9").inspect(), "1e-9");
+    }
+
+    #[test]
+    fn test_logical_short_circuit() {
+        let tests = vec![
+            ("false && missing", Object::FALSE),
+            ("true || missing", Object::TRUE),
+            ("let x = [1]; len(x) > 5 && x[10] > 0", Object::FALSE),
+            ("let x = 0; x != 0 && 10 / x > 1", Object::FALSE),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "input: {}", input);
+        }
+
+        let (_, output) =
+            test_eval_with_output(r#"true && puts("rhs"); false && puts("skipped");"#);
+
+        assert_eq!(output, "rhs\n");
+
+        match test_eval("true && missing") {
+            Object::Error(error) => assert_eq!(error.message, "identifier not found: missing"),
+            other => panic!("object is not Error. Got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bang_operator() {
+        let tests = vec![
+            ("!true", false),
+            ("!false", true),
+            ("!5", false),
+            ("!!true", true),
+            ("!!5", true),
+            ("!-5", false),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Boolean(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_if_else_expressions() {
+        let tests = vec![
+            ("if (true) { 10 }", Object::Integer(10)),
+            ("if (false) { 10 }", Object::Null),
+            ("if (1) { 10 }", Object::Integer(10)),
+            ("if (1 < 2) { 10 }", Object::Integer(10)),
+            ("if (1 > 2) { 10 }", Object::Null),
+            ("if (1 > 2) { 10 } else { 20 }", Object::Integer(20)),
+            ("if (1 < 2) { 10 } else { 20 }", Object::Integer(10)),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_return_statements() {
+        let tests = vec![
+            ("return 10;", Object::Integer(10)),
+            ("return 10; 9;", Object::Integer(10)),
+            ("return 2 * 5; 9;", Object::Integer(10)),
+            ("9; return 2 * 5; 9;", Object::Integer(10)),
+            ("return;", Object::Null),
+            (
+                "if (10 > 1) { if (10 > 1) { return 10; } return 1; }",
+                Object::Integer(10),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_let_statements() {
+        let tests = vec![
+            ("let a = 5; a;", 5),
+            ("let a = 5 * 5; a;", 25),
+            ("let a = 5; let b = a; b;", 5),
+            ("let a = 5; let b = a; let c = a + b + 5; c;", 15),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Integer(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_function_object() {
+        let input = "function(x) { x + 2; };";
+
+        let function = match test_eval(input) {
+            Object::Function(function) => function,
+            other => panic!("object is not Function. Got: {:?}", other),
+        };
+
+        assert_eq!(function.parameters.len(), 1);
+        assert_eq!(function.parameters[0].value, "x");
+        assert_eq!(function.inspect(), "function(x) { (x + 2) }");
+    }
+
+    #[test]
+    fn test_function_application() {
+        let tests = vec![
+            ("let identity = function(x) { x; }; identity(5);", 5),
+            ("let identity = function(x) { return x; }; identity(5);", 5),
+            ("let double = function(x) { x * 2; }; double(5);", 10),
+            ("let add = function(x, y) { x + y; }; add(5, 5);", 10),
+            (
+                "let add = function(x, y) { x + y; }; add(5 + 5, add(5, 5));",
+                20,
+            ),
+            ("function(x) { x; }(5)", 5),
+            ("let x = 1; let f = function(x) { x; }; f(2) + x;", 3),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Integer(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_closures() {
+        let tests = vec![
+            (
+                "let adder = function(x) { function(y) { x + y } }; let addTwo = adder(2); addTwo(3);",
+                5,
+            ),
+            (
+                "let compose = function(f, g) { function(x) { g(f(x)) } };
+                 let inc = function(x) { x + 1 };
+                 let double = function(x) { x * 2 };
+                 compose(inc, double)(5);",
+                12,
+            ),
+            (
+                "let fib = function(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(10);",
+                55,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Object::Integer(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_expressions() {
+        let tests = vec![
+            (
+                r#""Hello World!""#,
+                Object::String("Hello World!".to_string()),
+            ),
+            (
+                r#""Hello" + " " + "World!""#,
+                Object::String("Hello World!".to_string()),
+            ),
+            (
+                r#""tab\t" + "quote\"" + "\u{48}i""#,
+                Object::String("tab\tquote\"Hi".to_string()),
+            ),
+            (r#""a" == "a""#, Object::TRUE),
+            (r#""a" == "b""#, Object::FALSE),
+            (r#""a" != "b""#, Object::TRUE),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_array_literals() {
+        let input = "[1, 2 * 2, 3 + 3]";
+
+        assert_eq!(
+            test_eval(input),
+            Object::Array(vec![
+                Object::Integer(1),
+                Object::Integer(4),
+                Object::Integer(6)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_array_index_expressions() {
+        let tests = vec![
+            ("[1, 2, 3][0]", Object::Integer(1)),
+            ("[1, 2, 3][1]", Object::Integer(2)),
+            ("[1, 2, 3][2]", Object::Integer(3)),
+            ("let i = 0; [1][i];", Object::Integer(1)),
+            ("[1, 2, 3][1 + 1];", Object::Integer(3)),
+            ("let myArray = [1, 2, 3]; myArray[2];", Object::Integer(3)),
+            (
+                "let myArray = [1, 2, 3]; myArray[0] + myArray[1] + myArray[2];",
+                Object::Integer(6),
+            ),
+            ("[[1, 2], [3, [4]]][1][1][0]", Object::Integer(4)),
+            ("[1, 2, 3][3]", Object::Null),
+            ("[1, 2, 3][-1]", Object::Null),
+            ("[][0]", Object::Null),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_hash_literals() {
+        let input = r#"
+            let two = "two";
+            {
+                "one": 10 - 9,
+                two: 1 + 1,
+                "thr" + "ee": 6 / 2,
+                4: 4,
+                true: 5,
+                false: 6
+            }
+        "#;
+
+        let pairs = match test_eval(input) {
+            Object::Hash(pairs) => pairs,
+            other => panic!("object is not Hash. Got: {:?}", other),
+        };
+
+        let expected = vec![
+            (HashKey::String("one".to_string()), 1),
+            (HashKey::String("two".to_string()), 2),
+            (HashKey::String("three".to_string()), 3),
+            (HashKey::Integer(4), 4),
+            (HashKey::Boolean(true), 5),
+            (HashKey::Boolean(false), 6),
+        ];
+
+        assert_eq!(pairs.len(), expected.len());
+
+        for (key, value) in expected {
+            let pair = pairs.get(&key).expect("no pair for given key");
+
+            assert_eq!(pair.value, Object::Integer(value));
+        }
+    }
+
+    #[test]
+    fn test_hash_index_expressions() {
+        let tests = vec![
+            (r#"{"foo": 5}["foo"]"#, Object::Integer(5)),
+            (r#"{"foo": 5}["bar"]"#, Object::Null),
+            (r#"let key = "foo"; {"foo": 5}[key]"#, Object::Integer(5)),
+            (r#"{}["foo"]"#, Object::Null),
+            ("{5: 5}[5]", Object::Integer(5)),
+            ("{true: 5}[true]", Object::Integer(5)),
+            ("{false: 5}[false]", Object::Integer(5)),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_hash_inspect() {
+        let input = r#"{"b": 2, 1: [true], "a": "x"}"#;
+
+        assert_eq!(test_eval(input).inspect(), "{1: [true], a: x, b: 2}");
+    }
+
+    #[test]
+    fn test_builtin_functions() {
+        let tests = vec![
+            (r#"len("")"#, Object::Integer(0)),
+            (r#"len("four")"#, Object::Integer(4)),
+            ("len([1, 2, 3])", Object::Integer(3)),
+            ("first([1, 2, 3])", Object::Integer(1)),
+            ("last([1, 2, 3])", Object::Integer(3)),
+            (
+                "rest(rest([1, 2, 3]))",
+                Object::Array(vec![Object::Integer(3)]),
+            ),
+            (
+                "let a = [1]; let b = push(a, 2); len(a) + len(b);",
+                Object::Integer(3),
+            ),
+            (
+                "let len = function(x) { 42 }; len([1]);",
+                Object::Integer(42),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_puts_output() {
+        let input = r#"
+            let greet = function(name) { puts("hello " + name); };
+            greet("monkey");
+            puts(1, [true], {"a": 1});
+        "#;
+
+        assert_eq!(
+            test_eval_with_output(input),
+            (
+                Object::Null,
+                "hello monkey\n1\n[true]\n{a: 1}\n".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_error_handling() {
+        let tests = vec![
+            ("5 + true;", "type mismatch: INTEGER + BOOLEAN"),
+            ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
+            ("-true", "unknown operator: -BOOLEAN"),
+            ("true + false;", "unknown operator: BOOLEAN + BOOLEAN"),
+            (
+                "if (10 > 1) { true + false; }",
+                "unknown operator: BOOLEAN + BOOLEAN",
+            ),
+            (
+                "if (10 > 1) { if (10 > 1) { return true + false; } return 1; }",
+                "unknown operator: BOOLEAN + BOOLEAN",
+            ),
+            ("foobar", "identifier not found: foobar"),
+            ("10 / 0", "division by zero"),
+            ("10 % 0", "division by zero"),
+            ("2 ** -1", "negative exponent: -1"),
+            (
+                "let f = function(x) { x }; f(1, 2);",
+                "wrong number of arguments: want=1, got=2",
+            ),
+            ("5(1)", "not a function: INTEGER"),
+            (r#""Hello" - "World""#, "unknown operator: STRING - STRING"),
+            (r#""Hello" + 1"#, "type mismatch: STRING + INTEGER"),
+            ("1[0]", "index operator not supported: INTEGER[INTEGER]"),
+            (r#"[1]["0"]"#, "index operator not supported: ARRAY[STRING]"),
+            (
+                r#"{"name": "Monkey"}[function(x) { x }];"#,
+                "unusable as hash key: FUNCTION",
+            ),
+            ("{function(x) { x }: 1}", "unusable as hash key: FUNCTION"),
+            ("{[1]: 1}", "unusable as hash key: ARRAY"),
+            ("{1.5: 1}", "unusable as hash key: FLOAT"),
+            ("1.5 + true", "type mismatch: FLOAT + BOOLEAN"),
+            ("len(1)", "argument to `len` not supported, got INTEGER"),
+            (
+                r#"len("one", "two")"#,
+                "wrong number of arguments: want=1, got=2",
+            ),
+            ("let f = function() { y }; f();", "identifier not found: y"),
+        ];
+
+        for (input, expected) in tests {
+            match test_eval(input) {
+                Object::Error(error) => assert_eq!(error.message, expected, "input: {}", input),
+                other => panic!("object is not Error. Got: {:?}. input: {}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let sum = "let f = function(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } };";
+
+        assert_eq!(test_eval(&format!("{} f(100)", sum)), Object::Integer(100));
+
+        let tests = vec![
+            format!("{} f(1000)", sum),
+            "let f = function(n) { f(n + 1) }; f(0);".to_string(),
+        ];
+
+        for input in tests {
+            match test_eval(&input) {
+                Object::Error(error) => {
+                    assert_eq!(error.message, "stack overflow", "input: {}", input)
+                }
+                other => panic!("object is not Error. Got: {:?}. input: {}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_location() {
+        let tests = vec![
+            ("foobar", "foobar", 1, 1),
+            ("1 + 2;\nlet x = 5 + true;", "5 + true", 2, 9),
+            ("let f = function(x) {\n  x * y\n};\nf(2);", "y", 2, 7),
+            ("[1, 2, len(3)]", "len(3)", 1, 8),
+            ("if (-true) { 1 }", "-true", 1, 5),
+        ];
+
+        for (input, expected_source, expected_line, expected_column) in tests {
+            let error = match test_eval(input) {
+                Object::Error(error) => error,
+                other => panic!("object is not Error. Got: {:?}. input: {}", other, input),
+            };
+
+            let span = error.span.expect("error has no span");
+
+            assert_eq!(&input[span.start..span.end], expected_source);
+            assert_eq!((span.line, span.column), (expected_line, expected_column));
+        }
+    }
+}
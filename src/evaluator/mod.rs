@@ -0,0 +1,3 @@
+pub mod evaluator;
+
+pub use self::evaluator::*;
@@ -0,0 +1,75 @@
+use std::{error::Error, fmt};
+
+use crate::token::Span;
+
+/// Error found while lexing. Codes don't overlap with parse error codes
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    /// Character can't start any token: "let x = @;"
+    UnexpectedCharacter { ch: char, span: Span },
+    /// Input ended before closing quote: "\"abc"
+    UnterminatedString { span: Span },
+    /// Unknown or malformed escape sequence in string: "\"\\q\""
+    InvalidEscape { span: Span },
+    /// Input ended before block comment is closed: "/* abc"
+    UnterminatedComment { span: Span },
+    /// Number has prefix, but no digits: "0x"
+    MissingDigits { span: Span },
+    /// Digit is not valid for number radix: "0b102"
+    InvalidDigit { digit: char, radix: u32, span: Span },
+    /// Digit separator is not followed by digit: "1_"
+    TrailingSeparator { span: Span },
+}
+
+impl LexError {
+    /// Returns stable error code. Codes are never reused for other errors
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexError::UnexpectedCharacter { .. } => "E0101",
+            LexError::UnterminatedString { .. } => "E0102",
+            LexError::InvalidEscape { .. } => "E0103",
+            LexError::UnterminatedComment { .. } => "E0104",
+            LexError::MissingDigits { .. } => "E0105",
+            LexError::InvalidDigit { .. } => "E0106",
+            LexError::TrailingSeparator { .. } => "E0107",
+        }
+    }
+
+    /// Returns location of the invalid source fragment
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedCharacter { span, .. }
+            | LexError::UnterminatedString { span }
+            | LexError::InvalidEscape { span }
+            | LexError::UnterminatedComment { span }
+            | LexError::MissingDigits { span }
+            | LexError::InvalidDigit { span, .. }
+            | LexError::TrailingSeparator { span } => *span,
+        }
+    }
+
+    /// Returns error description without location
+    pub fn message(&self) -> String {
+        match self {
+            LexError::UnexpectedCharacter { ch, .. } => format!("unexpected character: {:?}", ch),
+            LexError::UnterminatedString { .. } => "unterminated string".to_string(),
+            LexError::InvalidEscape { .. } => "invalid escape sequence".to_string(),
+            LexError::UnterminatedComment { .. } => "unterminated block comment".to_string(),
+            LexError::MissingDigits { .. } => "number has no digits".to_string(),
+            LexError::InvalidDigit { digit, radix, .. } => {
+                format!("invalid digit {:?} in base {} number", digit, radix)
+            }
+            LexError::TrailingSeparator { .. } => {
+                "digit separator `_` must be followed by a digit".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span(), self.message())
+    }
+}
+
+impl Error for LexError {}
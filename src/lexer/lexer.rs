@@ -1,195 +1,640 @@
-use crate::token::{token, Kind, Token};
+use std::{borrow::Cow, mem};
 
-pub struct Lexer {
-    input: String,
-    position: usize,      // current character pos in input
-    read_position: usize, // next character pos in input
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use crate::token::{Kind, Span, Token, Trivia, TriviaKind};
+
+use super::LexError;
+
+/// Lexer settings. Default options are used by the interpreter
+#[derive(Default, Debug, Clone, Copy)]
+pub struct LexerOptions {
+    /// Keep whitespace and comments as trivia of the next token. Used by formatters
+    pub preserve_trivia: bool,
+    /// Allow Unicode XID identifiers: "naïve", "变量".
+    /// Letter numbers like "Ⅰ" are allowed only after the first character
+    pub unicode_identifiers: bool,
+}
+
+/// Lexer borrows its input. Token literals are slices of the input,
+/// only string literals with escape sequences allocate decoded value
+pub struct Lexer<'src> {
+    input: &'src str,
+    position: usize,      // byte offset of current character
+    read_position: usize, // byte offset of next character
     ch: char,             // current character
+    line: usize,          // line of current character
+    column: usize,        // column of current character
+
+    options: LexerOptions,
+    trivia: Vec<Trivia<'src>>, // trivia collected before the next token
+    errors: Vec<LexError>,     // errors of illegal tokens
+    is_done: bool,             // Eof was returned by iterator
 }
 
-impl Lexer {
+impl<'src> Lexer<'src> {
     const EMPTY_CHAR: char = '\0';
 
-    pub fn new(input: &str) -> Self {
+    pub fn new(input: &'src str) -> Self {
+        Self::with_options(input, LexerOptions::default())
+    }
+
+    pub fn with_options(input: &'src str, options: LexerOptions) -> Self {
         let mut lexer = Lexer {
-            input: input.to_string(),
+            input,
             position: 0,
             read_position: 0,
             ch: Self::EMPTY_CHAR,
+            line: 1,
+            column: 1,
+            options,
+            trivia: vec![],
+            errors: vec![],
+            is_done: false,
         };
         lexer.read_char();
 
-        return lexer;
+        lexer
     }
 
-    pub fn next_token(&mut self) -> Token {
-        let mut token = Token::default();
+    pub fn next_token(&mut self) -> Token<'src> {
+        let mut token = match self.skip_trivia() {
+            Some(comment) => self.unterminated_comment_token(comment),
+            None => self.read_token(),
+        };
 
-        self.skip_whitespace();
+        token.leading_trivia = mem::take(&mut self.trivia);
 
-        match self.ch {
-            ';' => {
-                token = self.new_token(Kind::Semicolon, self.ch);
-            }
-            '(' => {
-                token = self.new_token(Kind::Lparen, self.ch);
-            }
-            ')' => {
-                token = self.new_token(Kind::Rparen, self.ch);
-            }
-            ',' => {
-                token = self.new_token(Kind::Comma, self.ch);
-            }
-            '+' => {
-                token = self.new_token(Kind::Plus, self.ch);
-            }
-            '{' => {
-                token = self.new_token(Kind::Lbrace, self.ch);
-            }
-            '}' => {
-                token = self.new_token(Kind::Rbrace, self.ch);
-            }
-            '-' => {
-                token = self.new_token(Kind::Minus, self.ch);
-            }
+        token
+    }
+
+    fn read_token(&mut self) -> Token<'src> {
+        let start = self.current_span();
+
+        let kind = match self.ch {
+            ';' => Kind::Semicolon,
+            ':' => Kind::Colon,
+            '(' => Kind::Lparen,
+            ')' => Kind::Rparen,
+            ',' => Kind::Comma,
+            '+' => Kind::Plus,
+            '{' => Kind::Lbrace,
+            '}' => Kind::Rbrace,
+            '[' => Kind::Lbracket,
+            ']' => Kind::Rbracket,
+            '-' => Kind::Minus,
             '!' => {
                 if self.peek_char() == '=' {
                     self.read_char();
-                    token = Token {
-                        kind: Kind::Neq,
-                        literal: "!=".to_string(),
-                    }
+                    Kind::Neq
                 } else {
-                    token = self.new_token(Kind::Bang, self.ch);
+                    Kind::Bang
                 }
             }
             '*' => {
-                token = self.new_token(Kind::Asterisk, self.ch);
-            }
-            '/' => {
-                token = self.new_token(Kind::Slash, self.ch);
+                if self.peek_char() == '*' {
+                    self.read_char();
+                    Kind::Power
+                } else {
+                    Kind::Asterisk
+                }
             }
+            '%' => Kind::Percent,
+            '/' => Kind::Slash,
             '<' => {
-                token = self.new_token(Kind::Lt, self.ch);
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Kind::LtEq
+                } else {
+                    Kind::Lt
+                }
             }
             '>' => {
-                token = self.new_token(Kind::Gt, self.ch);
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Kind::GtEq
+                } else {
+                    Kind::Gt
+                }
+            }
+            '&' => {
+                if self.peek_char() == '&' {
+                    self.read_char();
+                    Kind::And
+                } else {
+                    self.unexpected_character(start)
+                }
+            }
+            '|' => {
+                if self.peek_char() == '|' {
+                    self.read_char();
+                    Kind::Or
+                } else {
+                    self.unexpected_character(start)
+                }
             }
             '=' => {
                 if self.peek_char() == '=' {
                     self.read_char();
-                    token = Token {
-                        kind: Kind::Eq,
-                        literal: "==".to_string(),
-                    };
+                    Kind::Eq
                 } else {
-                    token = self.new_token(Kind::Assign, self.ch);
+                    Kind::Assign
                 }
             }
+            '"' => match self.read_string(self.char_span(start)) {
+                Ok(value) => {
+                    self.read_char();
+
+                    return Token {
+                        kind: Kind::String,
+                        literal: value,
+                        span: start.to(self.current_span()),
+                        ..Default::default()
+                    };
+                }
+                // literal of illegal string is its raw source text
+                Err(error) => {
+                    self.errors.push(error);
+                    Kind::Illegal
+                }
+            },
             Self::EMPTY_CHAR => {
-                token.literal = "".to_string();
-                token.kind = Kind::Eof;
+                return Token {
+                    kind: Kind::Eof,
+                    literal: Cow::Borrowed(""),
+                    span: start,
+                    ..Default::default()
+                };
             }
             _ => {
-                if self.is_letter(self.ch) {
-                    token.literal = self.read_identifier();
-                    token.kind = Kind::lookup_ident(&token.literal);
-                    return token;
+                if self.is_identifier_start(self.ch) {
+                    let literal = self.read_identifier();
+
+                    return Token {
+                        kind: Kind::lookup_ident(literal),
+                        literal: Cow::Borrowed(literal),
+                        span: start.to(self.current_span()),
+                        ..Default::default()
+                    };
                 } else if self.is_digit(self.ch) {
-                    token.literal = self.read_number();
-                    token.kind = Kind::Number;
-                    return token;
+                    let kind = match self.read_number(start) {
+                        Ok(kind) => kind,
+                        Err(error) => {
+                            self.errors.push(error);
+                            Kind::Illegal
+                        }
+                    };
+
+                    let span = start.to(self.current_span());
+
+                    return Token {
+                        kind,
+                        literal: Cow::Borrowed(&self.input[span.start..span.end]),
+                        span,
+                        ..Default::default()
+                    };
                 } else {
-                    token = self.new_token(Kind::Illegal, self.ch);
+                    self.unexpected_character(start)
                 }
             }
-        }
+        };
 
         self.read_char();
 
-        return token;
+        let span = start.to(self.current_span());
+
+        Token {
+            kind,
+            literal: Cow::Borrowed(&self.input[span.start..span.end]),
+            span,
+            ..Default::default()
+        }
+    }
+
+    /// Records error for character which can't start any token
+    fn unexpected_character(&mut self, start: Span) -> Kind {
+        self.errors.push(LexError::UnexpectedCharacter {
+            ch: self.ch,
+            span: self.char_span(start),
+        });
+
+        Kind::Illegal
     }
 
-    fn new_token(&self, kind: token::Kind, ch: char) -> Token {
+    /// Returns illegal token for block comment which is not closed until the end of input
+    fn unterminated_comment_token(&mut self, comment: Span) -> Token<'src> {
+        self.errors.push(LexError::UnterminatedComment {
+            span: Span {
+                end: comment.start + 2,
+                ..comment
+            },
+        });
+
         Token {
-            kind: kind,
-            literal: ch.to_string(),
+            kind: Kind::Illegal,
+            literal: Cow::Borrowed(&self.input[comment.start..comment.end]),
+            span: comment,
+            ..Default::default()
+        }
+    }
+
+    /// Returns empty span at current character
+    fn current_span(&self) -> Span {
+        Span {
+            start: self.position,
+            end: self.position,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// Returns span from start to the end of current character
+    fn char_span(&self, start: Span) -> Span {
+        Span {
+            end: self.read_position,
+            ..start
         }
     }
 
-    /// Reads current character in input
+    /// Returns errors of illegal tokens returned so far
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Reads next character in input. Decodes only one character,
+    /// so lexing is linear in input size
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = Self::EMPTY_CHAR;
-        } else {
-            self.ch = self
-                .input
-                .chars()
-                .nth(self.read_position)
-                .expect("read_char failed read_position");
+        // move location past the previous character
+        if self.read_position > self.position {
+            if self.ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
         }
 
         self.position = self.read_position;
-        self.read_position += 1;
+
+        match self.input[self.read_position..].chars().next() {
+            Some(ch) => {
+                self.ch = ch;
+                self.read_position += ch.len_utf8();
+            }
+            None => self.ch = Self::EMPTY_CHAR,
+        }
     }
 
     /// Peeks next character in input
-    fn peek_char(&mut self) -> char {
-        if self.read_position >= self.input.len() {
-            return Self::EMPTY_CHAR;
-        } else {
-            return self
-                .input
-                .chars()
-                .nth(self.read_position)
-                .expect("peek_char failed read_position");
+    fn peek_char(&self) -> char {
+        self.peek_nth_char(0)
+    }
+
+    /// Peeks character n positions after the next one
+    fn peek_nth_char(&self, n: usize) -> char {
+        self.input[self.read_position..]
+            .chars()
+            .nth(n)
+            .unwrap_or(Self::EMPTY_CHAR)
+    }
+
+    /// Checks if char can start identifier: ASCII letter or _.
+    /// With unicode identifiers any XID_Start char is allowed, except letter numbers (Nl)
+    /// like "Ⅰ". XID_Start includes them, but they are excluded deliberately: as with
+    /// ASCII digits, number characters may continue identifier, but not start it
+    fn is_identifier_start(&self, ch: char) -> bool {
+        if self.options.unicode_identifiers && is_xid_start(ch) && !ch.is_numeric() {
+            return true;
         }
+
+        ch.is_ascii_alphabetic() || ch == '_'
     }
 
-    /// Checks if char is valid letter. Only ASCII and _
-    fn is_letter(&self, ch: char) -> bool {
-        return ch.is_ascii_alphabetic() || ch == '_';
+    /// Checks if char can continue identifier: identifier start or digit.
+    /// With unicode identifiers any XID_Continue char is allowed, including combining marks
+    fn is_identifier_continue(&self, ch: char) -> bool {
+        if self.options.unicode_identifiers && is_xid_continue(ch) {
+            return true;
+        }
+
+        self.is_identifier_start(ch) || self.is_digit(ch)
     }
 
     /// Checks if char is valid number.
     fn is_digit(&self, ch: char) -> bool {
-        return ch.is_ascii_digit();
+        ch.is_ascii_digit()
     }
 
     /// Reads identifier
-    fn read_identifier(&mut self) -> String {
+    fn read_identifier(&mut self) -> &'src str {
         let position = self.position;
 
-        while self.is_letter(self.ch) {
+        while self.is_identifier_continue(self.ch) {
             self.read_char();
         }
 
-        return self.input[position..self.position].to_string();
+        &self.input[position..self.position]
     }
 
-    /// Skips whitespaces
-    fn skip_whitespace(&mut self) {
-        while self.ch.is_ascii_whitespace() {
+    /// Reads string literal and decodes escape sequences: \n, \t, \r, \", \\, \u{1F600}.
+    /// Leaves current character at closing quote. String without escapes is borrowed from input.
+    /// Returns error if string is unterminated or has invalid escape
+    fn read_string(&mut self, quote: Span) -> Result<Cow<'src, str>, LexError> {
+        let start = self.read_position;
+
+        // decoded value, allocated only when the first escape is found
+        let mut value: Option<String> = None;
+        let mut invalid_escape = None;
+
+        loop {
             self.read_char();
+
+            match self.ch {
+                '"' => break,
+                Self::EMPTY_CHAR => return Err(LexError::UnterminatedString { span: quote }),
+                '\\' => {
+                    let escape = self.current_span();
+                    let value =
+                        value.get_or_insert_with(|| self.input[start..self.position].to_string());
+
+                    self.read_char();
+
+                    match self.ch {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        'r' => value.push('\r'),
+                        '"' => value.push('"'),
+                        '\\' => value.push('\\'),
+                        'u' => match self.read_unicode_escape() {
+                            Some(ch) => value.push(ch),
+                            None => {
+                                invalid_escape.get_or_insert(self.char_span(escape));
+                            }
+                        },
+                        Self::EMPTY_CHAR => {
+                            return Err(LexError::UnterminatedString { span: quote })
+                        }
+                        _ => {
+                            invalid_escape.get_or_insert(self.char_span(escape));
+                        }
+                    }
+                }
+                ch => {
+                    if let Some(value) = value.as_mut() {
+                        value.push(ch);
+                    }
+                }
+            }
+        }
+
+        if let Some(span) = invalid_escape {
+            return Err(LexError::InvalidEscape { span });
+        }
+
+        match value {
+            Some(value) => Ok(Cow::Owned(value)),
+            None => Ok(Cow::Borrowed(&self.input[start..self.position])),
         }
     }
 
-    /// Reads number
-    fn read_number(&mut self) -> String {
-        let position = self.position;
+    /// Reads "{XXXX}" part of unicode escape sequence.
+    /// Leaves current character at closing brace, so that string reading can continue
+    fn read_unicode_escape(&mut self) -> Option<char> {
+        if self.peek_char() != '{' {
+            return None;
+        }
+
+        self.read_char();
+
+        let start = self.read_position;
 
-        while self.is_digit(self.ch) {
+        while self.peek_char().is_ascii_hexdigit() {
             self.read_char();
         }
 
-        return self.input[position..self.position].to_string();
+        let code = &self.input[start..self.read_position];
+
+        if self.peek_char() != '}' {
+            return None;
+        }
+
+        self.read_char();
+
+        if code.is_empty() || code.len() > 6 {
+            return None;
+        }
+
+        u32::from_str_radix(code, 16).ok().and_then(char::from_u32)
+    }
+
+    /// Skips whitespaces and comments: "// line", "/* block /* nested */ */".
+    /// Collects them as trivia if enabled by options.
+    /// Returns span of block comment if it is not terminated
+    fn skip_trivia(&mut self) -> Option<Span> {
+        loop {
+            let start = self.current_span();
+
+            let kind = if self.ch.is_ascii_whitespace() {
+                while self.ch.is_ascii_whitespace() {
+                    self.read_char();
+                }
+
+                TriviaKind::Whitespace
+            } else if self.ch == '/' && self.peek_char() == '/' {
+                while self.ch != '\n' && self.ch != Self::EMPTY_CHAR {
+                    self.read_char();
+                }
+
+                TriviaKind::LineComment
+            } else if self.ch == '/' && self.peek_char() == '*' {
+                if !self.skip_block_comment() {
+                    return Some(start.to(self.current_span()));
+                }
+
+                TriviaKind::BlockComment
+            } else {
+                return None;
+            };
+
+            if self.options.preserve_trivia {
+                let span = start.to(self.current_span());
+
+                self.trivia.push(Trivia {
+                    kind,
+                    text: Cow::Borrowed(&self.input[span.start..span.end]),
+                    span,
+                });
+            }
+        }
+    }
+
+    /// Skips block comment including nested ones.
+    /// Returns false if input ends before comment is closed
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+
+        loop {
+            match (self.ch, self.peek_char()) {
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+
+                    if depth == 0 {
+                        self.read_char();
+                        return true;
+                    }
+                }
+                (Self::EMPTY_CHAR, _) => return false,
+                _ => {}
+            }
+
+            self.read_char();
+        }
+    }
+
+    /// Reads integer or float number: 42, 1_000, 0x1F, 0o17, 0b1010, 3.14, 1e-9, 2.5E+3.
+    /// Dot and exponent are part of the number only if followed by digits
+    fn read_number(&mut self, start: Span) -> Result<Kind, LexError> {
+        let radix = match (self.ch, self.peek_char()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'o' | 'O') => 8,
+            ('0', 'b' | 'B') => 2,
+            _ => 10,
+        };
+
+        if radix != 10 {
+            self.read_char();
+            self.read_char();
+
+            // all alphanumerics are read, so that invalid digits are reported as part of the number
+            while self.ch.is_ascii_alphanumeric() || self.ch == '_' {
+                self.read_char();
+            }
+
+            self.validate_digits(start, 2, radix)?;
+
+            return Ok(Kind::Number);
+        }
+
+        let mut kind = Kind::Number;
+
+        self.read_digits();
+
+        if self.ch == '.' && self.is_digit(self.peek_char()) {
+            kind = Kind::Float;
+            self.read_char();
+            self.read_digits();
+        }
+
+        let has_exponent = match (self.peek_char(), self.peek_nth_char(1)) {
+            (ch, _) if self.is_digit(ch) => true,
+            ('+' | '-', ch) => self.is_digit(ch),
+            _ => false,
+        };
+
+        if matches!(self.ch, 'e' | 'E') && has_exponent {
+            kind = Kind::Float;
+            self.read_char();
+
+            if matches!(self.ch, '+' | '-') {
+                self.read_char();
+            }
+
+            self.read_digits();
+        }
+
+        self.validate_digits(start, 0, 10)?;
+
+        Ok(kind)
+    }
+
+    /// Reads decimal digits and "_" separators
+    fn read_digits(&mut self) {
+        while self.is_digit(self.ch) || self.ch == '_' {
+            self.read_char();
+        }
+    }
+
+    /// Checks digits of the number read from start, skipping prefix of given length.
+    /// Number can contain only digits of its radix and "_" followed by digit
+    fn validate_digits(&self, start: Span, prefix: usize, radix: u32) -> Result<(), LexError> {
+        let digits = &self.input[start.start + prefix..self.position];
+
+        // numbers are ASCII, so byte offset is equal to column offset
+        let char_span = |offset: usize| Span {
+            start: start.start + prefix + offset,
+            end: start.start + prefix + offset + 1,
+            line: start.line,
+            column: start.column + prefix + offset,
+        };
+
+        for (offset, ch) in digits.char_indices() {
+            if ch == '_' {
+                let next = digits[offset + 1..].chars().next();
+
+                if !next.is_some_and(|next| next.is_digit(radix)) {
+                    return Err(LexError::TrailingSeparator {
+                        span: char_span(offset),
+                    });
+                }
+            } else if radix != 10 && !ch.is_digit(radix) {
+                return Err(LexError::InvalidDigit {
+                    digit: ch,
+                    radix,
+                    span: char_span(offset),
+                });
+            }
+        }
+
+        if digits.is_empty() {
+            return Err(LexError::MissingDigits {
+                span: start.to(self.current_span()),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Token<'src>;
+
+    /// Returns tokens up to and including Eof
+    fn next(&mut self) -> Option<Token<'src>> {
+        if self.is_done {
+            return None;
+        }
+
+        let token = self.next_token();
+        self.is_done = token.kind == Kind::Eof;
+
+        Some(token)
     }
 }
 
+/// Splits input into tokens, ending with Eof.
+/// Returns the first error if input has illegal tokens
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>, LexError> {
+    let mut lexer = Lexer::new(input);
+    let tokens: Vec<Token> = lexer.by_ref().collect();
+
+    match lexer.errors.first() {
+        Some(error) => Err(error.clone()),
+        None => Ok(tokens),
+    }
+}
+
+#[cfg(test)]
 mod test {
+    use std::borrow::Cow;
+
     use crate::token;
 
-    use super::Lexer;
+    use super::{is_xid_start, tokenize, LexError, Lexer, LexerOptions};
 
     #[test]
     fn test_next_token() {
@@ -203,7 +648,7 @@ mod test {
 
             let result = add(five, ten);
 
-            !-/*5;
+            !-/ *5;
             5 < 10 > 5;
 
             if (5 < 10) {
@@ -214,6 +659,11 @@ mod test {
 
             10 == 10;
             10 != 9;
+            a <= b >= c && d || e;
+            a % b ** c;
+            3.14 1e-9 2.5E+3 1.x;
+            [1, 2][0];
+            {\"foo\": \"bar\"}
         ";
 
         let tests = Vec::from([
@@ -291,6 +741,43 @@ mod test {
             (token::Kind::Neq, "!="),
             (token::Kind::Number, "9"),
             (token::Kind::Semicolon, ";"),
+            (token::Kind::Ident, "a"),
+            (token::Kind::LtEq, "<="),
+            (token::Kind::Ident, "b"),
+            (token::Kind::GtEq, ">="),
+            (token::Kind::Ident, "c"),
+            (token::Kind::And, "&&"),
+            (token::Kind::Ident, "d"),
+            (token::Kind::Or, "||"),
+            (token::Kind::Ident, "e"),
+            (token::Kind::Semicolon, ";"),
+            (token::Kind::Ident, "a"),
+            (token::Kind::Percent, "%"),
+            (token::Kind::Ident, "b"),
+            (token::Kind::Power, "**"),
+            (token::Kind::Ident, "c"),
+            (token::Kind::Semicolon, ";"),
+            (token::Kind::Float, "3.14"),
+            (token::Kind::Float, "1e-9"),
+            (token::Kind::Float, "2.5E+3"),
+            (token::Kind::Number, "1"),
+            (token::Kind::Illegal, "."),
+            (token::Kind::Ident, "x"),
+            (token::Kind::Semicolon, ";"),
+            (token::Kind::Lbracket, "["),
+            (token::Kind::Number, "1"),
+            (token::Kind::Comma, ","),
+            (token::Kind::Number, "2"),
+            (token::Kind::Rbracket, "]"),
+            (token::Kind::Lbracket, "["),
+            (token::Kind::Number, "0"),
+            (token::Kind::Rbracket, "]"),
+            (token::Kind::Semicolon, ";"),
+            (token::Kind::Lbrace, "{"),
+            (token::Kind::String, "foo"),
+            (token::Kind::Colon, ":"),
+            (token::Kind::String, "bar"),
+            (token::Kind::Rbrace, "}"),
             (token::Kind::Eof, ""),
         ]);
 
@@ -312,4 +799,484 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_string_token() {
+        let tests = vec![
+            (r#""foobar""#, token::Kind::String, "foobar"),
+            (r#""foo bar""#, token::Kind::String, "foo bar"),
+            (r#""""#, token::Kind::String, ""),
+            (r#""a\nb\tc""#, token::Kind::String, "a\nb\tc"),
+            (r#""say \"hi\"""#, token::Kind::String, "say \"hi\""),
+            (r#""back\\slash""#, token::Kind::String, "back\\slash"),
+            (
+                r#""\u{1F600}\u{e9}""#,
+                token::Kind::String,
+                "\u{1F600}\u{e9}",
+            ),
+            (r#""unterminated"#, token::Kind::Illegal, r#""unterminated"#),
+            (
+                r#""bad \q escape""#,
+                token::Kind::Illegal,
+                r#""bad \q escape""#,
+            ),
+            (r#""\u{110000}""#, token::Kind::Illegal, r#""\u{110000}""#),
+            (r#""ends with \"#, token::Kind::Illegal, r#""ends with \"#),
+        ];
+
+        for (input, expected_kind, expected_literal) in tests {
+            let mut lexer = Lexer::new(input);
+            let token = lexer.next_token();
+
+            assert_eq!(token.kind, expected_kind, "input: {}", input);
+            assert_eq!(token.literal, expected_literal, "input: {}", input);
+            assert_eq!(
+                lexer.next_token().kind,
+                token::Kind::Eof,
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_token_span() {
+        let input = "let x = 10;\n  \"hello\" == y\n";
+
+        let tests = vec![
+            (token::Kind::Let, 0, 3, 1, 1),
+            (token::Kind::Ident, 4, 5, 1, 5),
+            (token::Kind::Assign, 6, 7, 1, 7),
+            (token::Kind::Number, 8, 10, 1, 9),
+            (token::Kind::Semicolon, 10, 11, 1, 11),
+            (token::Kind::String, 14, 21, 2, 3),
+            (token::Kind::Eq, 22, 24, 2, 11),
+            (token::Kind::Ident, 25, 26, 2, 14),
+            (token::Kind::Eof, 27, 27, 3, 1),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (kind, start, end, line, column) in tests {
+            let token = lexer.next_token();
+
+            assert_eq!(token.kind, kind);
+            assert_eq!(
+                token.span,
+                token::Span {
+                    start,
+                    end,
+                    line,
+                    column
+                },
+                "token: {:?}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn test_non_ascii_input() {
+        let input = "let s = \"héllo 🐒\";\nlet é = s;";
+
+        let tests = vec![
+            (token::Kind::Let, "let", 0, 1, 1),
+            (token::Kind::Ident, "s", 4, 1, 5),
+            (token::Kind::Assign, "=", 6, 1, 7),
+            (token::Kind::String, "héllo 🐒", 8, 1, 9),
+            (token::Kind::Semicolon, ";", 21, 1, 18),
+            (token::Kind::Let, "let", 23, 2, 1),
+            (token::Kind::Illegal, "é", 27, 2, 5),
+            (token::Kind::Assign, "=", 30, 2, 7),
+            (token::Kind::Ident, "s", 32, 2, 9),
+            (token::Kind::Semicolon, ";", 33, 2, 10),
+            (token::Kind::Eof, "", 34, 2, 11),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (kind, literal, start, line, column) in tests {
+            let token = lexer.next_token();
+
+            assert_eq!(token.kind, kind);
+            assert_eq!(token.literal, literal);
+            assert_eq!(
+                (token.span.start, token.span.line, token.span.column),
+                (start, line, column),
+                "token: {:?}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn test_borrowed_literals() {
+        let input = r#"let s = "plain" + "esc\n";"#;
+
+        let tokens = tokenize(input).unwrap();
+
+        let owned: Vec<&str> = tokens
+            .iter()
+            .filter(|t| matches!(t.literal, Cow::Owned(_)))
+            .map(|t| t.literal.as_ref())
+            .collect();
+
+        assert_eq!(owned, vec!["esc\n"]);
+        assert_eq!(tokens[3].literal, "plain");
+    }
+
+    #[test]
+    fn test_iterator() {
+        let kinds: Vec<token::Kind> = Lexer::new("let x = 5;").map(|t| t.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                token::Kind::Let,
+                token::Kind::Ident,
+                token::Kind::Assign,
+                token::Kind::Number,
+                token::Kind::Semicolon,
+                token::Kind::Eof,
+            ]
+        );
+
+        let mut lexer = Lexer::new("").peekable();
+
+        assert_eq!(lexer.peek().map(|t| t.kind), Some(token::Kind::Eof));
+        assert!(lexer.next().is_some());
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        let tests = vec![
+            ("a & b", "E0101", "1:3: unexpected character: '&'", 2, 3),
+            (
+                "let x = @;",
+                "E0101",
+                "1:9: unexpected character: '@'",
+                8,
+                9,
+            ),
+            ("let s = \"abc", "E0102", "1:9: unterminated string", 8, 9),
+            (r#""a\qb\z""#, "E0103", "1:3: invalid escape sequence", 2, 4),
+            (r#""\u{zz}""#, "E0103", "1:2: invalid escape sequence", 1, 4),
+        ];
+
+        for (input, expected_code, expected_display, start, end) in tests {
+            let error = tokenize(input).expect_err(input);
+
+            assert_eq!(error.code(), expected_code, "input: {}", input);
+            assert_eq!(error.to_string(), expected_display, "input: {}", input);
+            assert_eq!((error.span().start, error.span().end), (start, end));
+        }
+
+        let mut lexer = Lexer::new("@ # $");
+        lexer.by_ref().for_each(drop);
+
+        assert_eq!(lexer.errors().len(), 3);
+        assert!(matches!(
+            lexer.errors()[2],
+            LexError::UnexpectedCharacter { ch: '$', .. }
+        ));
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "// header\nlet x = 5; // trailing\n/* block /* nested */ still */ x / 2";
+
+        let tokens: Vec<(token::Kind, String)> = Lexer::new(input)
+            .map(|t| (t.kind, t.literal.to_string()))
+            .collect();
+
+        let expected: Vec<(token::Kind, String)> = vec![
+            (token::Kind::Let, "let"),
+            (token::Kind::Ident, "x"),
+            (token::Kind::Assign, "="),
+            (token::Kind::Number, "5"),
+            (token::Kind::Semicolon, ";"),
+            (token::Kind::Ident, "x"),
+            (token::Kind::Slash, "/"),
+            (token::Kind::Number, "2"),
+            (token::Kind::Eof, ""),
+        ]
+        .into_iter()
+        .map(|(kind, literal)| (kind, literal.to_string()))
+        .collect();
+
+        assert_eq!(tokens, expected);
+
+        let mut lexer = Lexer::new("x /* open /* nested */");
+        let tokens: Vec<token::Kind> = lexer.by_ref().map(|t| t.kind).collect();
+
+        assert_eq!(
+            tokens,
+            vec![token::Kind::Ident, token::Kind::Illegal, token::Kind::Eof]
+        );
+        assert_eq!(
+            lexer.errors()[0].to_string(),
+            "1:3: unterminated block comment"
+        );
+    }
+
+    #[test]
+    fn test_trivia() {
+        let input = "// doc\nlet /* c */ x;\n";
+        let options = LexerOptions {
+            preserve_trivia: true,
+            ..Default::default()
+        };
+
+        let tokens: Vec<token::Token> = Lexer::with_options(input, options).collect();
+
+        let trivia: Vec<Vec<(token::TriviaKind, &str)>> = tokens
+            .iter()
+            .map(|t| {
+                t.leading_trivia
+                    .iter()
+                    .map(|trivia| (trivia.kind, trivia.text.as_ref()))
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(
+            trivia,
+            vec![
+                vec![
+                    (token::TriviaKind::LineComment, "// doc"),
+                    (token::TriviaKind::Whitespace, "\n"),
+                ],
+                vec![
+                    (token::TriviaKind::Whitespace, " "),
+                    (token::TriviaKind::BlockComment, "/* c */"),
+                    (token::TriviaKind::Whitespace, " "),
+                ],
+                vec![],
+                vec![(token::TriviaKind::Whitespace, "\n")],
+            ]
+        );
+
+        let tokens: Vec<token::Token> = Lexer::new(input).collect();
+
+        assert!(tokens.iter().all(|t| t.leading_trivia.is_empty()));
+    }
+
+    #[test]
+    fn test_number_literals() {
+        let input = "0x1F 0XfF 0o17 0b1010 1_000_000 0x_1f 1_0.2_5e1_0 007";
+
+        let tokens: Vec<(token::Kind, String)> = tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.kind, t.literal.to_string()))
+            .collect();
+
+        let expected: Vec<(token::Kind, String)> = vec![
+            (token::Kind::Number, "0x1F"),
+            (token::Kind::Number, "0XfF"),
+            (token::Kind::Number, "0o17"),
+            (token::Kind::Number, "0b1010"),
+            (token::Kind::Number, "1_000_000"),
+            (token::Kind::Number, "0x_1f"),
+            (token::Kind::Float, "1_0.2_5e1_0"),
+            (token::Kind::Number, "007"),
+            (token::Kind::Eof, ""),
+        ]
+        .into_iter()
+        .map(|(kind, literal)| (kind, literal.to_string()))
+        .collect();
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_number_errors() {
+        let tests = vec![
+            ("0x", "E0105", "1:1: number has no digits", 0, 2, "0x"),
+            (
+                "x = 0b102;",
+                "E0106",
+                "1:9: invalid digit '2' in base 2 number",
+                8,
+                9,
+                "0b102",
+            ),
+            (
+                "0o8",
+                "E0106",
+                "1:3: invalid digit '8' in base 8 number",
+                2,
+                3,
+                "0o8",
+            ),
+            (
+                "0xfg",
+                "E0106",
+                "1:4: invalid digit 'g' in base 16 number",
+                3,
+                4,
+                "0xfg",
+            ),
+            (
+                "1_",
+                "E0107",
+                "1:2: digit separator `_` must be followed by a digit",
+                1,
+                2,
+                "1_",
+            ),
+            (
+                "1__0",
+                "E0107",
+                "1:2: digit separator `_` must be followed by a digit",
+                1,
+                2,
+                "1__0",
+            ),
+            (
+                "1_.5",
+                "E0107",
+                "1:2: digit separator `_` must be followed by a digit",
+                1,
+                2,
+                "1_.5",
+            ),
+            (
+                "0b_",
+                "E0107",
+                "1:3: digit separator `_` must be followed by a digit",
+                2,
+                3,
+                "0b_",
+            ),
+        ];
+
+        for (input, expected_code, expected_display, start, end, expected_literal) in tests {
+            let mut lexer = Lexer::new(input);
+            let tokens: Vec<token::Token> = lexer.by_ref().collect();
+
+            let error = lexer.errors().first().expect(input);
+
+            assert_eq!(error.code(), expected_code, "input: {}", input);
+            assert_eq!(error.to_string(), expected_display, "input: {}", input);
+            assert_eq!((error.span().start, error.span().end), (start, end));
+
+            let illegal = tokens
+                .iter()
+                .find(|t| t.kind == token::Kind::Illegal)
+                .expect(input);
+
+            assert_eq!(illegal.literal, expected_literal, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_identifiers_with_digits() {
+        let literals: Vec<(token::Kind, String)> = Lexer::new("x1 = _2y3 + x 1")
+            .map(|t| (t.kind, t.literal.to_string()))
+            .collect();
+
+        let expected: Vec<(token::Kind, String)> = vec![
+            (token::Kind::Ident, "x1"),
+            (token::Kind::Assign, "="),
+            (token::Kind::Ident, "_2y3"),
+            (token::Kind::Plus, "+"),
+            (token::Kind::Ident, "x"),
+            (token::Kind::Number, "1"),
+            (token::Kind::Eof, ""),
+        ]
+        .into_iter()
+        .map(|(kind, literal)| (kind, literal.to_string()))
+        .collect();
+
+        assert_eq!(literals, expected);
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let input = "let naïve = 变量2 + é;";
+        let options = LexerOptions {
+            unicode_identifiers: true,
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (token::Kind::Let, "let", 0, 3, 1),
+            (token::Kind::Ident, "naïve", 4, 10, 5),
+            (token::Kind::Assign, "=", 11, 12, 11),
+            (token::Kind::Ident, "变量2", 13, 20, 13),
+            (token::Kind::Plus, "+", 21, 22, 17),
+            (token::Kind::Ident, "é", 23, 25, 19),
+            (token::Kind::Semicolon, ";", 25, 26, 20),
+            (token::Kind::Eof, "", 26, 26, 21),
+        ];
+
+        let mut lexer = Lexer::with_options(input, options);
+
+        for (kind, literal, start, end, column) in tests {
+            let token = lexer.next_token();
+
+            assert_eq!(token.kind, kind);
+            assert_eq!(token.literal, literal);
+            assert_eq!(&input[token.span.start..token.span.end], literal);
+            assert_eq!(
+                (token.span.start, token.span.end, token.span.column),
+                (start, end, column)
+            );
+        }
+
+        assert!(lexer.errors().is_empty());
+
+        let result = tokenize(input);
+
+        assert!(matches!(
+            result,
+            Err(LexError::UnexpectedCharacter { ch: 'ï', .. })
+        ));
+    }
+
+    #[test]
+    fn test_unicode_identifier_tables() {
+        let options = LexerOptions {
+            unicode_identifiers: true,
+            ..Default::default()
+        };
+
+        // combining marks continue identifier: decomposed "naïve" and Devanagari virama
+        let tests = vec![
+            ("nai\u{0308}ve", vec![(token::Kind::Ident, "nai\u{0308}ve")]),
+            ("क्षत्रिय", vec![(token::Kind::Ident, "क्षत्रिय")]),
+            (
+                "x²",
+                vec![(token::Kind::Ident, "x"), (token::Kind::Illegal, "²")],
+            ),
+            // letter number is XID_Start, but deliberately can't start identifier
+            (
+                "Ⅰx",
+                vec![(token::Kind::Illegal, "Ⅰ"), (token::Kind::Ident, "x")],
+            ),
+            ("xⅠ", vec![(token::Kind::Ident, "xⅠ")]),
+        ];
+
+        assert!(is_xid_start('Ⅰ'));
+
+        for (input, expected) in tests {
+            let mut lexer = Lexer::with_options(input, options);
+
+            for (kind, literal) in expected {
+                let token = lexer.next_token();
+
+                assert_eq!(token.kind, kind, "input: {}", input);
+                assert_eq!(token.literal, literal, "input: {}", input);
+            }
+
+            assert_eq!(
+                lexer.next_token().kind,
+                token::Kind::Eof,
+                "input: {}",
+                input
+            );
+        }
+    }
 }
@@ -1,3 +1,5 @@
+pub mod error;
 pub mod lexer;
 
+pub use self::error::*;
 pub use self::lexer::*;
@@ -0,0 +1,13 @@
+#![allow(clippy::module_inception)]
+
+pub mod ast;
+pub mod compiler;
+pub mod diagnostics;
+pub mod evaluator;
+pub mod lexer;
+pub mod object;
+pub mod parser;
+pub mod repl;
+pub mod runner;
+pub mod token;
+pub mod vm;
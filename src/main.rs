@@ -1,9 +1,20 @@
-mod ast;
-mod lexer;
-mod parser;
-mod repl;
-mod token;
+use std::{env, process::ExitCode};
 
-fn main() {
-    repl::start()
+use monkey_lang_rust::{repl, runner};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [] => {
+            repl::start();
+            ExitCode::SUCCESS
+        }
+        [flag, path] if flag == "--disassemble" => runner::disassemble_file(path),
+        [path] => runner::run_file(path),
+        _ => {
+            eprintln!("usage: monkey-lang-rust [--disassemble] [file]");
+            ExitCode::FAILURE
+        }
+    }
 }
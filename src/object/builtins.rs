@@ -0,0 +1,238 @@
+use std::io::Write;
+
+use super::{Object, RuntimeError};
+
+/// Signature of native functions. Output is used by functions that print, like puts
+pub type BuiltinFunction = fn(&[Object], &mut dyn Write) -> Object;
+
+/// Registry of builtin functions. Order is stable, so index can be used to refer to builtin
+pub const BUILTINS: &[(&str, BuiltinFunction)] = &[
+    ("len", len),
+    ("puts", puts),
+    ("first", first),
+    ("last", last),
+    ("rest", rest),
+    ("push", push),
+];
+
+/// Looks up builtin function by name
+pub fn get_builtin(name: &str) -> Option<Object> {
+    BUILTINS
+        .iter()
+        .find(|(builtin_name, _)| *builtin_name == name)
+        .map(|(name, function)| Object::Builtin(Builtin::new(name, *function)))
+}
+
+/// Native function value
+#[derive(Debug, Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub function: BuiltinFunction,
+}
+
+impl Builtin {
+    pub fn new(name: &'static str, function: BuiltinFunction) -> Self {
+        Builtin { name, function }
+    }
+
+    pub fn call(&self, arguments: &[Object], output: &mut dyn Write) -> Object {
+        (self.function)(arguments, output)
+    }
+}
+
+// Builtins are unique by name, comparing function pointers is not reliable
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+/// Returns length of string (in characters) or array: len("abc"), len([1, 2])
+fn len(arguments: &[Object], _: &mut dyn Write) -> Object {
+    if let Some(error) = check_arguments_count(arguments, 1) {
+        return error;
+    }
+
+    match &arguments[0] {
+        Object::String(value) => Object::Integer(value.chars().count() as i64),
+        Object::Array(elements) => Object::Integer(elements.len() as i64),
+        other => Object::Error(RuntimeError::new(format!(
+            "argument to `len` not supported, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+/// Prints arguments on separate lines: puts("hello", 1)
+fn puts(arguments: &[Object], output: &mut dyn Write) -> Object {
+    for argument in arguments {
+        if let Err(error) = writeln!(output, "{}", argument.inspect()) {
+            return Object::Error(RuntimeError::new(format!(
+                "failed to write output: {}",
+                error
+            )));
+        }
+    }
+
+    Object::Null
+}
+
+/// Returns first element of array or null if it's empty: first([1, 2])
+fn first(arguments: &[Object], _: &mut dyn Write) -> Object {
+    match get_array_argument("first", arguments) {
+        Ok(elements) => elements.first().cloned().unwrap_or(Object::Null),
+        Err(error) => error,
+    }
+}
+
+/// Returns last element of array or null if it's empty: last([1, 2])
+fn last(arguments: &[Object], _: &mut dyn Write) -> Object {
+    match get_array_argument("last", arguments) {
+        Ok(elements) => elements.last().cloned().unwrap_or(Object::Null),
+        Err(error) => error,
+    }
+}
+
+/// Returns new array without first element or null if it's empty: rest([1, 2])
+fn rest(arguments: &[Object], _: &mut dyn Write) -> Object {
+    match get_array_argument("rest", arguments) {
+        Ok([]) => Object::Null,
+        Ok([_, rest @ ..]) => Object::Array(rest.to_vec()),
+        Err(error) => error,
+    }
+}
+
+/// Returns new array with value added to the end: push([1, 2], 3)
+fn push(arguments: &[Object], _: &mut dyn Write) -> Object {
+    if let Some(error) = check_arguments_count(arguments, 2) {
+        return error;
+    }
+
+    match &arguments[0] {
+        Object::Array(elements) => {
+            let mut elements = elements.clone();
+            elements.push(arguments[1].clone());
+
+            Object::Array(elements)
+        }
+        other => Object::Error(RuntimeError::new(format!(
+            "argument to `push` must be ARRAY, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+fn check_arguments_count(arguments: &[Object], want: usize) -> Option<Object> {
+    if arguments.len() != want {
+        return Some(Object::Error(RuntimeError::new(format!(
+            "wrong number of arguments: want={}, got={}",
+            want,
+            arguments.len()
+        ))));
+    }
+
+    None
+}
+
+fn get_array_argument<'a>(name: &str, arguments: &'a [Object]) -> Result<&'a [Object], Object> {
+    if let Some(error) = check_arguments_count(arguments, 1) {
+        return Err(error);
+    }
+
+    match &arguments[0] {
+        Object::Array(elements) => Ok(elements),
+        other => Err(Object::Error(RuntimeError::new(format!(
+            "argument to `{}` must be ARRAY, got {}",
+            name,
+            other.object_type()
+        )))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{get_builtin, Object, RuntimeError};
+
+    fn call(name: &str, arguments: &[Object]) -> (Object, String) {
+        let builtin = match get_builtin(name) {
+            Some(Object::Builtin(builtin)) => builtin,
+            _ => panic!("builtin {} not found", name),
+        };
+
+        let mut output = vec![];
+        let result = builtin.call(arguments, &mut output);
+
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_builtins() {
+        let array = Object::Array(vec![Object::Integer(1), Object::Integer(2)]);
+        let empty = Object::Array(vec![]);
+
+        let tests = vec![
+            (
+                "len",
+                vec![Object::String("héllo".to_string())],
+                Object::Integer(5),
+            ),
+            ("len", vec![array.clone()], Object::Integer(2)),
+            ("first", vec![array.clone()], Object::Integer(1)),
+            ("first", vec![empty.clone()], Object::Null),
+            ("last", vec![array.clone()], Object::Integer(2)),
+            ("last", vec![empty.clone()], Object::Null),
+            (
+                "rest",
+                vec![array.clone()],
+                Object::Array(vec![Object::Integer(2)]),
+            ),
+            ("rest", vec![empty.clone()], Object::Null),
+            (
+                "push",
+                vec![empty, Object::Integer(3)],
+                Object::Array(vec![Object::Integer(3)]),
+            ),
+            (
+                "len",
+                vec![Object::Integer(1)],
+                Object::Error(RuntimeError::new(
+                    "argument to `len` not supported, got INTEGER".to_string(),
+                )),
+            ),
+            (
+                "len",
+                vec![array.clone(), array],
+                Object::Error(RuntimeError::new(
+                    "wrong number of arguments: want=1, got=2".to_string(),
+                )),
+            ),
+            (
+                "first",
+                vec![Object::Integer(1)],
+                Object::Error(RuntimeError::new(
+                    "argument to `first` must be ARRAY, got INTEGER".to_string(),
+                )),
+            ),
+        ];
+
+        for (name, arguments, expected) in tests {
+            assert_eq!(
+                call(name, &arguments).0,
+                expected,
+                "{}({:?})",
+                name,
+                arguments
+            );
+        }
+    }
+
+    #[test]
+    fn test_puts() {
+        let arguments = vec![Object::String("hello".to_string()), Object::Integer(1)];
+
+        assert_eq!(
+            call("puts", &arguments),
+            (Object::Null, "hello\n1\n".to_string())
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use super::Object;
+
+/// Storage of bindings created by let statements and function calls.
+/// Lookups fall back to the outer (enclosing) environment.
+#[derive(Default)]
+pub struct Environment {
+    store: HashMap<String, Object>,
+    outer: Option<Rc<RefCell<Environment>>>,
+    output: Option<Rc<RefCell<dyn Write>>>, // sink for builtins like puts
+    call_depth: usize,                      // number of nested function calls
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Environment {
+            store: HashMap::new(),
+            outer: None,
+            output: None,
+            call_depth: 0,
+        }
+    }
+
+    /// Creates environment which writes program output to the given sink instead of stdout
+    pub fn with_output(output: Rc<RefCell<dyn Write>>) -> Self {
+        Environment {
+            store: HashMap::new(),
+            outer: None,
+            output: Some(output),
+            call_depth: 0,
+        }
+    }
+
+    /// Creates environment which extends outer one. Used for function calls,
+    /// call depth is the depth of the caller plus one
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>, call_depth: usize) -> Self {
+        Environment {
+            store: HashMap::new(),
+            outer: Some(outer),
+            output: None,
+            call_depth,
+        }
+    }
+
+    pub fn call_depth(&self) -> usize {
+        self.call_depth
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.outer.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Returns output sink of this or outer environment. Defaults to stdout
+    pub fn output(&self) -> Rc<RefCell<dyn Write>> {
+        match (&self.output, &self.outer) {
+            (Some(output), _) => Rc::clone(output),
+            (None, Some(outer)) => outer.borrow().output(),
+            (None, None) => Rc::new(RefCell::new(io::stdout())),
+        }
+    }
+
+    /// Binds value in current environment. Outer bindings are shadowed, not changed
+    pub fn set(&mut self, name: &str, value: Object) -> Object {
+        self.store.insert(name.to_string(), value.clone());
+        value
+    }
+}
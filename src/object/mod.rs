@@ -0,0 +1,7 @@
+pub mod builtins;
+pub mod environment;
+pub mod object;
+
+pub use self::builtins::*;
+pub use self::environment::*;
+pub use self::object::*;
@@ -0,0 +1,208 @@
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+use crate::{
+    ast::{ast::Node, BlockStatement, Identifier},
+    compiler::{Instructions, SourceMap},
+    token::Span,
+};
+
+use super::{Builtin, Environment};
+
+/// Runtime value produced by evaluating AST nodes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Null,
+    ReturnValue(Box<Object>),
+    Error(RuntimeError),
+    Function(Rc<Function>),
+    String(String),
+    Array(Vec<Object>),
+    Hash(HashMap<HashKey, HashPair>),
+    Builtin(Builtin),
+    CompiledFunction(Rc<CompiledFunction>),
+    Closure(Rc<Closure>),
+}
+
+impl Object {
+    pub const TRUE: Object = Object::Boolean(true);
+    pub const FALSE: Object = Object::Boolean(false);
+
+    /// Returns name of the object type used in error messages
+    pub fn object_type(&self) -> &'static str {
+        match self {
+            Object::Integer(_) => "INTEGER",
+            Object::Float(_) => "FLOAT",
+            Object::Boolean(_) => "BOOLEAN",
+            Object::Null => "NULL",
+            Object::ReturnValue(_) => "RETURN_VALUE",
+            Object::Error(_) => "ERROR",
+            Object::Function(_) => "FUNCTION",
+            Object::String(_) => "STRING",
+            Object::Array(_) => "ARRAY",
+            Object::Hash(_) => "HASH",
+            Object::Builtin(_) => "BUILTIN",
+            Object::CompiledFunction(_) => "COMPILED_FUNCTION",
+            // closures are functions of the virtual machine
+            Object::Closure(_) => "FUNCTION",
+        }
+    }
+
+    /// Returns printable representation of the object
+    pub fn inspect(&self) -> String {
+        match self {
+            Object::Integer(v) => v.to_string(),
+            // debug format keeps fraction for whole numbers: 2.0
+            Object::Float(v) => format!("{:?}", v),
+            Object::Boolean(v) => v.to_string(),
+            Object::Null => "null".to_string(),
+            Object::ReturnValue(v) => v.inspect(),
+            Object::Error(error) => format!("ERROR: {}", error),
+            Object::Function(function) => function.inspect(),
+            Object::String(value) => value.clone(),
+            Object::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.inspect()).collect();
+
+                format!("[{}]", elements.join(", "))
+            }
+            Object::Hash(pairs) => {
+                // sort by key, so that output doesn't depend on hash map order
+                let mut pairs: Vec<(&HashKey, &HashPair)> = pairs.iter().collect();
+                pairs.sort_by(|a, b| a.0.cmp(b.0));
+
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(_, pair)| format!("{}: {}", pair.key.inspect(), pair.value.inspect()))
+                    .collect();
+
+                format!("{{{}}}", pairs.join(", "))
+            }
+            Object::Builtin(builtin) => format!("builtin function {}", builtin.name),
+            Object::CompiledFunction(function) => {
+                format!("compiled function({} parameters)", function.num_parameters)
+            }
+            Object::Closure(closure) => {
+                format!("closure({} parameters)", closure.function.num_parameters)
+            }
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Object::Error(_))
+    }
+
+    /// Returns key for hash objects. Only integers, booleans and strings are hashable
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Object::Integer(v) => Some(HashKey::Integer(*v)),
+            Object::Boolean(v) => Some(HashKey::Boolean(*v)),
+            Object::String(v) => Some(HashKey::String(v.clone())),
+            _ => None,
+        }
+    }
+
+    /// Everything except false and null is truthy
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Boolean(false) | Object::Null)
+    }
+}
+
+/// Error raised during evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+    pub span: Option<Span>, // expression which caused the error
+}
+
+impl RuntimeError {
+    pub fn new(message: String) -> Self {
+        RuntimeError {
+            message,
+            span: None,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{}: {}", span, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Key of the hash object derived from hashable object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HashKey {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+}
+
+/// Entry of the hash object. Original key is kept for printing
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashPair {
+    pub key: Object,
+    pub value: Object,
+}
+
+/// Function value. Captures environment where it was defined to support closures
+#[derive(Clone)]
+pub struct Function {
+    pub parameters: Vec<Identifier>,
+    pub body: BlockStatement,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+impl Function {
+    pub fn inspect(&self) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.to_str()).collect();
+
+        format!("function({}) {}", parameters.join(", "), self.body.to_str())
+    }
+}
+
+// Environment is skipped, because it may contain function itself
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("parameters", &self.parameters)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+// Functions are equal only if they are the same literal captured in the same environment
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.env, &other.env) && self.inspect() == other.inspect()
+    }
+}
+
+/// Function compiled to bytecode. Stored in constant pool
+#[derive(Debug, Clone)]
+pub struct CompiledFunction {
+    pub instructions: Instructions,
+    pub source_map: SourceMap,
+    pub num_locals: usize, // including parameters
+    pub num_parameters: usize,
+}
+
+// Source map is debug information, functions with the same code are equal
+impl PartialEq for CompiledFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.instructions == other.instructions
+            && self.num_locals == other.num_locals
+            && self.num_parameters == other.num_parameters
+    }
+}
+
+/// Compiled function with values of variables captured from enclosing functions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub free: Vec<Object>,
+}
@@ -0,0 +1,129 @@
+use std::{error::Error, fmt};
+
+use crate::{
+    lexer::LexError,
+    token::{Kind, Span},
+};
+
+/// Error found while parsing. Every kind has stable code, which tools can rely on
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// Next token is not the one grammar requires: "let 5 = x;"
+    UnexpectedToken {
+        expected: Kind,
+        found: Kind,
+        span: Span,
+    },
+    /// Token can't start an expression: "let x = ;"
+    NoPrefixParseFn { kind: Kind, span: Span },
+    /// Lexer could not recognize token: "let x = @;". Keeps code and span of lexer error
+    Lex(LexError),
+    /// Number literal doesn't fit into integer: "99999999999999999999"
+    InvalidInteger { literal: String, span: Span },
+    /// Input ended before closing brace: "if (x) { x"
+    UnterminatedBlock { span: Span },
+    /// Number literal is not a valid float
+    InvalidFloat { literal: String, span: Span },
+}
+
+impl ParseError {
+    /// Returns stable error code. Codes are never reused for other errors
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "E0001",
+            ParseError::NoPrefixParseFn { .. } => "E0002",
+            ParseError::Lex(error) => error.code(),
+            ParseError::InvalidInteger { .. } => "E0004",
+            ParseError::UnterminatedBlock { .. } => "E0005",
+            ParseError::InvalidFloat { .. } => "E0007",
+        }
+    }
+
+    /// Returns location of the token which caused error
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::NoPrefixParseFn { span, .. }
+            | ParseError::InvalidInteger { span, .. }
+            | ParseError::UnterminatedBlock { span }
+            | ParseError::InvalidFloat { span, .. } => *span,
+            ParseError::Lex(error) => error.span(),
+        }
+    }
+
+    /// Returns error description without location
+    pub fn message(&self) -> String {
+        match self {
+            ParseError::UnexpectedToken {
+                expected, found, ..
+            } => format!(
+                "expected next token: {:?}. Got token: {:?}",
+                expected, found
+            ),
+            ParseError::NoPrefixParseFn { kind, .. } => {
+                format!("no prefix parse function for {:?} found", kind)
+            }
+            ParseError::Lex(error) => error.message(),
+            ParseError::InvalidInteger { literal, .. } => {
+                format!("could not parse {} as integer", literal)
+            }
+            ParseError::UnterminatedBlock { .. } => {
+                "unterminated block: expected Rbrace. Got token: Eof".to_string()
+            }
+            ParseError::InvalidFloat { literal, .. } => {
+                format!("could not parse {} as float", literal)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span(), self.message())
+    }
+}
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod test {
+    use crate::token::{Kind, Span};
+
+    use super::ParseError;
+
+    #[test]
+    fn test_display() {
+        let span = Span {
+            start: 10,
+            end: 13,
+            line: 1,
+            column: 11,
+        };
+
+        let tests = vec![
+            (
+                ParseError::UnexpectedToken {
+                    expected: Kind::Semicolon,
+                    found: Kind::Let,
+                    span,
+                },
+                "E0001",
+                "1:11: expected next token: Semicolon. Got token: Let",
+            ),
+            (
+                ParseError::InvalidInteger {
+                    literal: "99999999999999999999".to_string(),
+                    span,
+                },
+                "E0004",
+                "1:11: could not parse 99999999999999999999 as integer",
+            ),
+        ];
+
+        for (error, expected_code, expected_display) in tests {
+            assert_eq!(error.code(), expected_code);
+            assert_eq!(error.span(), span);
+            assert_eq!(error.to_string(), expected_display);
+        }
+    }
+}
@@ -1 +1,5 @@
-pub mod parser;
\ No newline at end of file
+pub mod error;
+pub mod parser;
+
+pub use self::error::*;
+pub use self::parser::*;
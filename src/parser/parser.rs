@@ -1,28 +1,52 @@
+use std::mem;
+
 use crate::{
-    ast::{ast::Program, expression, statement, Expression, ExpressionStatement, Statement},
+    ast::{
+        ast::{Node, Program},
+        expression, statement, BlockStatement, ErrorExpression, ErrorStatement, Expression,
+        ExpressionStatement, Statement,
+    },
     lexer::Lexer,
     token::{Kind, Token},
 };
 
-pub struct Parser {
-    lexer: Lexer,
+use super::ParseError;
+
+pub struct Parser<'src> {
+    lexer: Lexer<'src>,
 
-    curr_token: Token,
-    peek_token: Token,
+    curr_token: Token<'src>,
+    peek_token: Token<'src>,
 
-    errors: Vec<String>,
+    errors: Vec<ParseError>,
+
+    // number of blocks being parsed, used by error recovery
+    block_depth: usize,
+    // recovery stopped on "}" closing current block, which is left for the block
+    at_block_end: bool,
 }
 
 /// Operator precedence
 #[derive(PartialEq, Eq, PartialOrd, Debug)]
 enum Precedence {
     Lowest,
+    LogicalOr,   // ||
+    LogicalAnd,  // &&
     Equals,      // ==
-    LessGreater, // < or >
+    LessGreater, // <, >, <= or >=
     Sum,         // +
-    Product,     // *
+    Product,     // *, / or %
     Prefix,      // -foo or !foo
+    Power,       // **
     Call,        // foo_function()
+    Index,       // array[index]
+}
+
+/// Operator associativity. Defines how operators of the same precedence are grouped
+#[derive(PartialEq, Eq, Debug)]
+enum Associativity {
+    Left,  // a - b - c == (a - b) - c
+    Right, // a ** b ** c == a ** (b ** c)
 }
 
 impl Precedence {
@@ -32,22 +56,55 @@ impl Precedence {
             Kind::Neq => Precedence::Equals,
             Kind::Lt => Precedence::LessGreater,
             Kind::Gt => Precedence::LessGreater,
+            Kind::LtEq => Precedence::LessGreater,
+            Kind::GtEq => Precedence::LessGreater,
+            Kind::And => Precedence::LogicalAnd,
+            Kind::Or => Precedence::LogicalOr,
             Kind::Plus => Precedence::Sum,
             Kind::Minus => Precedence::Sum,
             Kind::Slash => Precedence::Product,
             Kind::Asterisk => Precedence::Product,
+            Kind::Percent => Precedence::Product,
+            Kind::Power => Precedence::Power,
+            Kind::Lparen => Precedence::Call,
+            Kind::Lbracket => Precedence::Index,
             _ => Precedence::Lowest,
         }
     }
+
+    fn get_associativity_for_token(kind: Kind) -> Associativity {
+        match kind {
+            Kind::Power => Associativity::Right,
+            _ => Associativity::Left,
+        }
+    }
+
+    /// Returns previous precedence level
+    fn lower(&self) -> Precedence {
+        match self {
+            Precedence::Lowest | Precedence::LogicalOr => Precedence::Lowest,
+            Precedence::LogicalAnd => Precedence::LogicalOr,
+            Precedence::Equals => Precedence::LogicalAnd,
+            Precedence::LessGreater => Precedence::Equals,
+            Precedence::Sum => Precedence::LessGreater,
+            Precedence::Product => Precedence::Sum,
+            Precedence::Prefix => Precedence::Product,
+            Precedence::Power => Precedence::Prefix,
+            Precedence::Call => Precedence::Power,
+            Precedence::Index => Precedence::Call,
+        }
+    }
 }
 
-impl Parser {
-    pub fn new(lexer: Lexer) -> Self {
+impl<'src> Parser<'src> {
+    pub fn new(lexer: Lexer<'src>) -> Self {
         let mut parser = Parser {
-            lexer: lexer,
+            lexer,
             curr_token: Token::default(),
             peek_token: Token::default(),
             errors: vec![],
+            block_depth: 0,
+            at_block_end: false,
         };
 
         // read 2 next tokens to set both curr_token and peek_token
@@ -61,11 +118,8 @@ impl Parser {
         let mut program = Program { statements: vec![] };
 
         while !self.is_curr_token(Kind::Eof) {
-            let stmt = self.parse_statement();
-
-            if let Some(stmt_val) = stmt {
-                program.statements.push(stmt_val)
-            }
+            let stmt = self.parse_statement_or_error();
+            program.statements.push(stmt);
 
             self.next_token();
         }
@@ -73,9 +127,32 @@ impl Parser {
         program
     }
 
+    /// Advances tokens. Errors of illegal tokens found by lexer are reported as parse errors
     fn next_token(&mut self) {
-        self.curr_token = self.peek_token.clone();
-        self.peek_token = self.lexer.next_token();
+        let reported = self.lexer.errors().len();
+
+        self.curr_token = mem::replace(&mut self.peek_token, self.lexer.next_token());
+
+        for error in &self.lexer.errors()[reported..] {
+            self.errors.push(ParseError::Lex(error.clone()));
+        }
+    }
+
+    /// Parses statement. If it fails, skips the rest of the statement
+    /// and returns error placeholder covering skipped tokens
+    fn parse_statement_or_error(&mut self) -> Statement {
+        let span = self.curr_token.span;
+
+        match self.parse_statement() {
+            Some(stmt) => stmt,
+            None => {
+                self.synchronize();
+
+                Statement::Error(ErrorStatement {
+                    span: span.to(self.curr_token.span),
+                })
+            }
+        }
     }
 
     fn parse_statement(&mut self) -> Option<Statement> {
@@ -87,27 +164,10 @@ impl Parser {
     }
 
     fn parse_expression(&mut self, precedence: Precedence) -> Option<Expression> {
-        let left_expr = self.get_parsed_prefix_expression(self.curr_token.kind);
-
-        if left_expr.is_none() {
-            let message = format!(
-                "no prefix parse function for {:?} found",
-                self.curr_token.kind
-            );
-            self.errors.push(message);
-            return None;
-        }
-
-        let mut left_expr = left_expr.unwrap();
+        let mut left_expr = self.get_parsed_prefix_expression(self.curr_token.kind)?;
 
         while !self.is_peek_token(Kind::Semicolon) && precedence < self.peek_precedence() {
-            let infix = self.get_parsed_infix_expression(self.peek_token.kind, left_expr.clone());
-
-            if infix.is_none() {
-                return Some(left_expr);
-            }
-
-            left_expr = infix.unwrap();
+            left_expr = self.get_parsed_infix_expression(self.peek_token.kind, left_expr)?;
         }
 
         Some(left_expr)
@@ -116,12 +176,31 @@ impl Parser {
     fn get_parsed_prefix_expression(&mut self, kind: Kind) -> Option<Expression> {
         match kind {
             Kind::Ident => Some(Expression::Identifier(expression::Identifier {
-                token: self.curr_token.clone(),
-                value: self.curr_token.literal.clone(),
+                value: self.curr_token.literal.to_string(),
+                span: self.curr_token.span,
             })),
             Kind::Number => self.parse_integer_literal(),
+            Kind::Float => self.parse_float_literal(),
+            Kind::String => Some(Expression::StringLiteral(expression::StringLiteral {
+                value: self.curr_token.literal.to_string(),
+                span: self.curr_token.span,
+            })),
             Kind::Bang | Kind::Minus => self.parse_prefix_expression(),
-            _ => None,
+            Kind::True | Kind::False => self.parse_boolean(),
+            Kind::Lparen => self.parse_grouped_expression(),
+            Kind::If => self.parse_if_expression(),
+            Kind::Function => self.parse_function_literal(),
+            Kind::Lbracket => self.parse_array_literal(),
+            Kind::Lbrace => self.parse_hash_literal(),
+            // error of illegal token is already taken from lexer
+            Kind::Illegal => None,
+            _ => {
+                self.errors.push(ParseError::NoPrefixParseFn {
+                    kind,
+                    span: self.curr_token.span,
+                });
+                None
+            }
         }
     }
 
@@ -131,117 +210,173 @@ impl Parser {
             | Kind::Minus
             | Kind::Slash
             | Kind::Asterisk
+            | Kind::Percent
+            | Kind::Power
             | Kind::Eq
             | Kind::Neq
             | Kind::Lt
-            | Kind::Gt => self.parse_infix_expression(left),
+            | Kind::Gt
+            | Kind::LtEq
+            | Kind::GtEq
+            | Kind::And
+            | Kind::Or => self.parse_infix_expression(left),
+            Kind::Lparen => self.parse_call_expression(left),
+            Kind::Lbracket => self.parse_index_expression(left),
             _ => None,
         }
     }
 
+    /// Parses value of let, return or expression statement.
+    /// If it fails, skips the rest of the statement and returns error placeholder
+    fn parse_statement_value(&mut self) -> Expression {
+        let span = self.curr_token.span;
+
+        match self.parse_expression(Precedence::Lowest) {
+            Some(expr) => expr,
+            None => {
+                self.synchronize();
+
+                Expression::Error(ErrorExpression {
+                    span: span.to(self.curr_token.span),
+                })
+            }
+        }
+    }
+
     /// Parses let statements: "let foo = 5;"
     fn parse_let_statement(&mut self) -> Option<Statement> {
-        let kind = self.curr_token.clone();
+        let span = self.curr_token.span;
 
         if !self.expect_peek(Kind::Ident) {
             return None;
         }
 
         let identifier = expression::Identifier {
-            token: self.curr_token.clone(),
-            value: self.curr_token.literal.clone(),
+            value: self.curr_token.literal.to_string(),
+            span: self.curr_token.span,
         };
 
         if !self.expect_peek(Kind::Assign) {
             return None;
         }
 
+        self.next_token();
+
+        let value = self.parse_statement_value();
+
+        // statement end is already skipped by recovery
+        if !matches!(value, Expression::Error(_)) && !self.expect_statement_end() {
+            return None;
+        }
+
         let let_stmt = statement::LetStatement {
-            token: kind,
+            span: span.to(self.curr_token.span),
             name: identifier,
-            value: Some(Expression::Identifier(expression::Identifier {
-                token: Token {
-                    kind: Kind::Ident,
-                    literal: "".to_string(),
-                },
-                value: "".to_string(),
-            })),
+            value: Some(value),
         };
 
-        while !self.is_curr_token(Kind::Semicolon) {
-            self.next_token();
-        }
-
         Some(Statement::LetStatement(let_stmt))
     }
 
-    /// Parses return statements: "return foo;"
+    /// Parses return statements: "return foo;", "return;"
     fn parse_return_statement(&mut self) -> Option<Statement> {
-        let token = self.curr_token.clone();
+        let span = self.curr_token.span;
 
-        self.next_token();
+        let mut return_value = None;
 
-        while !self.is_curr_token(Kind::Semicolon) {
+        // bare "return;" returns null
+        if !self.is_peek_statement_end() {
             self.next_token();
+            return_value = Some(self.parse_statement_value());
+        }
+
+        let recovered = matches!(return_value, Some(Expression::Error(_)));
+
+        if !recovered && !self.expect_statement_end() {
+            return None;
         }
 
         let return_stmt = statement::ReturnStatement {
-            token,
-            return_value: None,
+            span: span.to(self.curr_token.span),
+            return_value,
         };
 
         Some(Statement::ReturnStatement(return_stmt))
     }
 
     fn parse_expression_statement(&mut self) -> Option<Statement> {
-        let token = self.curr_token.clone();
-        let expression = self.parse_expression(Precedence::Lowest);
-
-        let stmt = ExpressionStatement { token, expression };
+        let span = self.curr_token.span;
+        let expression = self.parse_statement_value();
 
-        if self.is_peek_token(Kind::Semicolon) {
+        if !self.at_block_end && self.is_peek_token(Kind::Semicolon) {
             self.next_token()
         }
 
+        let stmt = ExpressionStatement {
+            span: span.to(self.curr_token.span),
+            expression: Some(expression),
+        };
+
         Some(Statement::ExpressionStatement(stmt))
     }
 
     // Parses integer literals: 1, 5, 100, etc.
     fn parse_integer_literal(&mut self) -> Option<Expression> {
-        let token = self.curr_token.clone();
-        let value = self.curr_token.literal.clone().parse::<i64>();
-
-        if value.is_err() {
-            let message = format!("could not parse {} as integer", self.curr_token.literal);
-            self.errors.push(message);
-            return None;
-        }
+        let value = match parse_integer(&self.curr_token.literal) {
+            Some(value) => value,
+            None => {
+                self.errors.push(ParseError::InvalidInteger {
+                    literal: self.curr_token.literal.to_string(),
+                    span: self.curr_token.span,
+                });
+                return None;
+            }
+        };
 
+        // literal keeps original spelling, so that to_str returns "0x1F", not "31"
         let literal = expression::IntegerLiteral {
-            token,
-            value: value.unwrap(),
+            literal: self.curr_token.literal.to_string(),
+            value,
+            span: self.curr_token.span,
         };
 
         Some(Expression::IntegerLiteral(literal))
     }
 
+    // Parses float literals: 3.14, 1e-9, etc.
+    fn parse_float_literal(&mut self) -> Option<Expression> {
+        let value = match self.curr_token.literal.replace('_', "").parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => {
+                self.errors.push(ParseError::InvalidFloat {
+                    literal: self.curr_token.literal.to_string(),
+                    span: self.curr_token.span,
+                });
+                return None;
+            }
+        };
+
+        Some(Expression::FloatLiteral(expression::FloatLiteral {
+            literal: self.curr_token.literal.to_string(),
+            value,
+            span: self.curr_token.span,
+        }))
+    }
+
     // Parsed prefix expressions: -100, !foo, etc.
     fn parse_prefix_expression(&mut self) -> Option<Expression> {
         let mut expr = expression::PrefixExpression {
-            token: self.curr_token.clone(),
-            operator: self.curr_token.literal.clone(),
+            operator: self.curr_token.literal.to_string(),
             right: None,
+            span: self.curr_token.span,
         };
 
         // Move to "right" side
         self.next_token();
 
-        match self.parse_expression(Precedence::Prefix) {
-            Some(val) => expr.right = Some(Box::new(val)),
-            None => self
-                .errors
-                .push("No value found in prefix right side.".to_string()),
-        }
+        expr.right = Some(Box::new(self.parse_expression(Precedence::Prefix)?));
+
+        expr.span = expr.span.to(self.curr_token.span);
 
         Some(Expression::PrefixExpression(expr))
     }
@@ -250,246 +385,765 @@ impl Parser {
         self.next_token();
 
         let mut expr = expression::InfixExpression {
-            token: self.curr_token.clone(),
-            operator: self.curr_token.literal.clone(),
+            operator: self.curr_token.literal.to_string(),
+            span: left.span(),
             left: Some(Box::new(left)),
             right: None,
         };
 
-        let precedence = self.curr_precedence();
+        // right side of right-associative operator is parsed with lower precedence,
+        // so that it takes following operators of the same precedence
+        let precedence = match Precedence::get_associativity_for_token(self.curr_token.kind) {
+            Associativity::Left => self.curr_precedence(),
+            Associativity::Right => self.curr_precedence().lower(),
+        };
+
         self.next_token();
 
-        if let Some(right_val) = self.parse_expression(precedence) {
-            expr.right = Some(Box::new(right_val));
-        }
+        expr.right = Some(Box::new(self.parse_expression(precedence)?));
 
-        Some(Expression::InfixExpression(expr))
-    }
+        expr.span = expr.span.to(self.curr_token.span);
 
-    fn expect_peek(&mut self, expected: Kind) -> bool {
-        if self.is_peek_token(expected) {
-            self.next_token();
-            true
-        } else {
-            self.peek_error(expected);
-            false
-        }
+        Some(Expression::InfixExpression(expr))
     }
 
-    fn is_curr_token(&self, expected: Kind) -> bool {
-        self.curr_token.kind == expected
-    }
+    // Parses boolean literals: true, false
+    fn parse_boolean(&mut self) -> Option<Expression> {
+        let literal = expression::Boolean {
+            value: self.is_curr_token(Kind::True),
+            span: self.curr_token.span,
+        };
 
-    fn is_peek_token(&self, expected: Kind) -> bool {
-        self.peek_token.kind == expected
+        Some(Expression::Boolean(literal))
     }
 
-    fn peek_error(&mut self, token: Kind) {
-        let msg = format!(
-            "expected next token: {:?}. Got token: {:?}",
-            token, self.peek_token.kind
-        );
-        self.errors.push(msg);
-    }
+    // Parses expressions in parentheses: (5 + 5) * 2
+    fn parse_grouped_expression(&mut self) -> Option<Expression> {
+        self.next_token();
 
-    fn errors(&self) -> Vec<String> {
-        self.errors.clone()
-    }
+        let expr = self.parse_expression(Precedence::Lowest)?;
 
-    fn peek_precedence(&self) -> Precedence {
-        let precedence = Precedence::get_precedence_for_token(self.peek_token.kind);
+        if !self.expect_peek(Kind::Rparen) {
+            return None;
+        }
 
-        precedence
+        Some(expr)
     }
 
-    fn curr_precedence(&self) -> Precedence {
-        let precedence = Precedence::get_precedence_for_token(self.curr_token.kind);
-
-        precedence
-    }
-}
+    // Parses if expressions: if (x < y) { x } else { y }
+    fn parse_if_expression(&mut self) -> Option<Expression> {
+        let span = self.curr_token.span;
 
-#[cfg(test)]
-mod test {
-    use crate::{
-        ast::{ast::Node, Expression, Statement},
-        lexer,
-    };
+        if !self.expect_peek(Kind::Lparen) {
+            return None;
+        }
 
-    use super::Parser;
+        self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
 
-    #[test]
-    fn test_let_statements() {
-        let input = "
-        let x = 5;
-        let y = 10;
-        let foo = 999;
-        ";
+        if !self.expect_peek(Kind::Rparen) {
+            return None;
+        }
 
-        let lexer = lexer::Lexer::new(input);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
+        if !self.expect_peek(Kind::Lbrace) {
+            return None;
+        }
 
-        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+        let consequence = self.parse_block_statement()?;
+        let mut alternative = None;
 
-        let tests = Vec::from(["x".to_string(), "y".to_string(), "foo".to_string()]);
+        if self.is_peek_token(Kind::Else) {
+            self.next_token();
 
-        for (idx, expected_name) in tests.iter().enumerate() {
-            let stmt = program.statements.get(idx).unwrap();
+            if !self.expect_peek(Kind::Lbrace) {
+                return None;
+            }
 
-            assert_eq!(stmt.token_literal(), "let");
+            alternative = Some(self.parse_block_statement()?);
+        }
 
-            let stmt = match stmt {
-                Statement::LetStatement(v) => v,
-                _ => unreachable!(),
-            };
+        let expr = expression::IfExpression {
+            span: span.to(self.curr_token.span),
+            condition: Box::new(condition),
+            consequence,
+            alternative,
+        };
 
-            assert_eq!(&stmt.name.value, expected_name);
-            assert_eq!(&stmt.name.token_literal(), expected_name);
-        }
+        Some(Expression::IfExpression(expr))
     }
 
-    #[test]
-    fn test_return_statement() {
-        let input = "
-            return 5;
-        ";
+    // Parses function literals: function(x, y) { return x + y; }
+    fn parse_function_literal(&mut self) -> Option<Expression> {
+        let span = self.curr_token.span;
 
-        let lexer = lexer::Lexer::new(input);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
+        if !self.expect_peek(Kind::Lparen) {
+            return None;
+        }
 
-        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+        let parameters = self.parse_function_parameters()?;
 
-        for stmt in program.statements {
-            match stmt {
-                Statement::ReturnStatement(_) => assert_eq!(stmt.token_literal(), "return"),
-                Statement::ExpressionStatement(_) => assert_eq!(stmt.token_literal(), "5"),
-                _ => unreachable!(),
-            }
+        if !self.expect_peek(Kind::Lbrace) {
+            return None;
         }
-    }
 
-    #[test]
-    fn test_identifier_expression() {
-        let input = "foobar;";
+        let body = self.parse_block_statement()?;
 
-        let lexer = lexer::Lexer::new(input);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
+        let literal = expression::FunctionLiteral {
+            span: span.to(self.curr_token.span),
+            parameters,
+            body,
+        };
 
-        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+        Some(Expression::FunctionLiteral(literal))
+    }
 
-        assert_eq!(
-            program.statements.len(),
-            1,
-            "program has not enough statements"
-        );
+    /// Parses comma separated identifiers. Expects current token to be "("
+    fn parse_function_parameters(&mut self) -> Option<Vec<expression::Identifier>> {
+        let mut identifiers = vec![];
 
-        let stmt = program.statements.first().unwrap();
+        if self.is_peek_token(Kind::Rparen) {
+            self.next_token();
+            return Some(identifiers);
+        }
 
-        let stmt = match stmt {
-            Statement::ExpressionStatement(v) => v,
-            _ => panic!("statement not ExpressionStatement"),
-        };
+        if !self.expect_peek(Kind::Ident) {
+            return None;
+        }
 
-        let expr = stmt.expression.as_ref().expect("expression not Some");
+        identifiers.push(expression::Identifier {
+            value: self.curr_token.literal.to_string(),
+            span: self.curr_token.span,
+        });
 
-        let ident = match expr {
-            Expression::Identifier(v) => v,
-            _ => panic!("expression not Identifier"),
-        };
+        while self.is_peek_token(Kind::Comma) {
+            self.next_token();
 
-        assert_eq!(ident.value, "foobar");
-        assert_eq!(ident.token_literal(), "foobar");
-    }
+            if !self.expect_peek(Kind::Ident) {
+                return None;
+            }
 
-    #[test]
-    fn test_integer_literal_expression() {
-        let input = "5;";
+            identifiers.push(expression::Identifier {
+                value: self.curr_token.literal.to_string(),
+                span: self.curr_token.span,
+            });
+        }
 
-        let lexer = lexer::Lexer::new(input);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
+        if !self.expect_peek(Kind::Rparen) {
+            return None;
+        }
 
-        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+        Some(identifiers)
+    }
 
-        assert_eq!(
-            program.statements.len(),
-            1,
-            "program has not enough statements"
-        );
+    // Parses call expressions: add(1, 2 * 3)
+    fn parse_call_expression(&mut self, function: Expression) -> Option<Expression> {
+        self.next_token();
 
-        let stmt = program.statements.first().unwrap();
+        let arguments = self.parse_expression_list(Kind::Rparen)?;
 
-        let stmt = match stmt {
-            Statement::ExpressionStatement(v) => v,
-            _ => panic!("statement not ExpressionStatement"),
+        let expr = expression::CallExpression {
+            span: function.span().to(self.curr_token.span),
+            function: Box::new(function),
+            arguments,
         };
 
-        let expr = stmt.expression.as_ref().expect("expression not Some");
+        Some(Expression::CallExpression(expr))
+    }
 
-        let literal = match expr {
-            Expression::IntegerLiteral(v) => v,
-            _ => panic!("expression not IntegerLiteral"),
+    // Parses array literals: [1, 2 * 2, foo]
+    fn parse_array_literal(&mut self) -> Option<Expression> {
+        let span = self.curr_token.span;
+        let elements = self.parse_expression_list(Kind::Rbracket)?;
+
+        let literal = expression::ArrayLiteral {
+            span: span.to(self.curr_token.span),
+            elements,
         };
 
-        assert_eq!(literal.value, 5);
-        assert_eq!(literal.token_literal(), "5");
+        Some(Expression::ArrayLiteral(literal))
     }
 
-    #[test]
-    fn test_prefix_expression() {
-        let tests = vec![("!5", "!", 5), ("-10", "-", 10)];
+    // Parses hash literals: {"name": "x", 1: true}.
+    // Braces in expression position are always hash literals,
+    // blocks are parsed only after if, else and function
+    fn parse_hash_literal(&mut self) -> Option<Expression> {
+        let span = self.curr_token.span;
+        let mut pairs = vec![];
 
-        for (input, expected_prefix, expected_right) in tests {
-            let lexer = lexer::Lexer::new(input);
-            let mut parser = Parser::new(lexer);
-            let program = parser.parse_program();
+        while !self.is_peek_token(Kind::Rbrace) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::Lowest)?;
 
-            for error in parser.errors() {
-                println!("ERROR: {}", error);
+            if !self.expect_peek(Kind::Colon) {
+                return None;
             }
 
-            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+            self.next_token();
+            let value = self.parse_expression(Precedence::Lowest)?;
 
-            assert_eq!(
-                program.statements.len(),
-                1,
-                "program has not enough statements"
-            );
+            pairs.push((key, value));
 
-            let stmt = program.statements.first().unwrap();
+            if !self.is_peek_token(Kind::Rbrace) && !self.expect_peek(Kind::Comma) {
+                return None;
+            }
+        }
 
-            let stmt = match stmt {
-                Statement::ExpressionStatement(v) => v,
-                _ => panic!("statement not ExpressionStatement"),
-            };
+        if !self.expect_peek(Kind::Rbrace) {
+            return None;
+        }
 
-            let expr = stmt.expression.as_ref().expect("expression not Some");
+        let literal = expression::HashLiteral {
+            span: span.to(self.curr_token.span),
+            pairs,
+        };
 
-            let prefix_expr = match expr {
-                Expression::PrefixExpression(v) => v,
-                _ => panic!("expression not PrefixExpression"),
-            };
+        Some(Expression::HashLiteral(literal))
+    }
 
-            assert_eq!(prefix_expr.operator, expected_prefix);
+    // Parses index expressions: array[1 + 1]
+    fn parse_index_expression(&mut self, left: Expression) -> Option<Expression> {
+        self.next_token();
+        self.next_token();
 
-            // test integer literal
-            if let Some(right_expr) = prefix_expr.right.as_deref() {
-                let int_literal = match right_expr {
-                    Expression::IntegerLiteral(t) => t,
-                    _ => panic!("not integer literal"),
-                };
+        let index = self.parse_expression(Precedence::Lowest)?;
 
-                assert_eq!(int_literal.value, expected_right);
-            }
+        if !self.expect_peek(Kind::Rbracket) {
+            return None;
         }
+
+        let expr = expression::IndexExpression {
+            span: left.span().to(self.curr_token.span),
+            left: Box::new(left),
+            index: Box::new(index),
+        };
+
+        Some(Expression::IndexExpression(expr))
     }
 
-    #[test]
-    fn test_infix_expression() {
-        let tests = vec![
+    /// Parses comma separated expressions until end token.
+    /// Expects current token to be opening token: "(" or "["
+    fn parse_expression_list(&mut self, end: Kind) -> Option<Vec<Expression>> {
+        let mut list = vec![];
+
+        if self.is_peek_token(end) {
+            self.next_token();
+            return Some(list);
+        }
+
+        self.next_token();
+        list.push(self.parse_expression(Precedence::Lowest)?);
+
+        while self.is_peek_token(Kind::Comma) {
+            self.next_token();
+            self.next_token();
+            list.push(self.parse_expression(Precedence::Lowest)?);
+        }
+
+        if !self.expect_peek(end) {
+            return None;
+        }
+
+        Some(list)
+    }
+
+    /// Parses statements between braces. Expects current token to be "{"
+    fn parse_block_statement(&mut self) -> Option<BlockStatement> {
+        let mut block = BlockStatement {
+            statements: vec![],
+            span: self.curr_token.span,
+        };
+
+        self.next_token();
+        self.block_depth += 1;
+
+        while !self.is_curr_token(Kind::Rbrace) {
+            if self.is_curr_token(Kind::Eof) {
+                self.errors.push(ParseError::UnterminatedBlock {
+                    span: self.curr_token.span,
+                });
+                self.block_depth -= 1;
+                return None;
+            }
+
+            let stmt = self.parse_statement_or_error();
+            block.statements.push(stmt);
+
+            if !mem::take(&mut self.at_block_end) {
+                self.next_token();
+            }
+        }
+
+        self.block_depth -= 1;
+        block.span = block.span.to(self.curr_token.span);
+
+        Some(block)
+    }
+
+    /// Skips tokens until the end of the failed statement, so that one error
+    /// does not cascade into the following ones. Stops on ";", on or before
+    /// "}" closing current block, or before "let", "return" or "if"
+    fn synchronize(&mut self) {
+        // braces opened by skipped tokens
+        let mut depth = 0;
+
+        while !self.is_curr_token(Kind::Eof) {
+            match self.curr_token.kind {
+                Kind::Lbrace => depth += 1,
+                Kind::Rbrace if depth > 0 => depth -= 1,
+                Kind::Rbrace if self.block_depth > 0 => {
+                    self.at_block_end = true;
+                    return;
+                }
+                Kind::Semicolon if depth == 0 => return,
+                _ => {}
+            }
+
+            if depth == 0 {
+                match self.peek_token.kind {
+                    Kind::Let | Kind::Return | Kind::If | Kind::Eof => return,
+                    Kind::Rbrace if self.block_depth > 0 => return,
+                    _ => {}
+                }
+            }
+
+            self.next_token();
+        }
+    }
+
+    fn expect_peek(&mut self, expected: Kind) -> bool {
+        if self.is_peek_token(expected) {
+            self.next_token();
+            true
+        } else {
+            self.peek_error(expected);
+            false
+        }
+    }
+
+    /// Consumes trailing semicolon of a statement.
+    /// Semicolon can be omitted only before end of block or end of input.
+    fn expect_statement_end(&mut self) -> bool {
+        if self.is_peek_token(Kind::Semicolon) {
+            self.next_token();
+            return true;
+        }
+
+        if self.is_peek_statement_end() {
+            return true;
+        }
+
+        self.peek_error(Kind::Semicolon);
+        false
+    }
+
+    fn is_peek_statement_end(&self) -> bool {
+        matches!(
+            self.peek_token.kind,
+            Kind::Semicolon | Kind::Rbrace | Kind::Eof
+        )
+    }
+
+    fn is_curr_token(&self, expected: Kind) -> bool {
+        self.curr_token.kind == expected
+    }
+
+    fn is_peek_token(&self, expected: Kind) -> bool {
+        self.peek_token.kind == expected
+    }
+
+    fn peek_error(&mut self, token: Kind) {
+        // illegal token is already reported by lexer
+        if self.is_peek_token(Kind::Illegal) {
+            return;
+        }
+
+        self.errors.push(ParseError::UnexpectedToken {
+            expected: token,
+            found: self.peek_token.kind,
+            span: self.peek_token.span,
+        });
+    }
+
+    /// Returns errors collected during parsing
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    fn peek_precedence(&self) -> Precedence {
+        Precedence::get_precedence_for_token(self.peek_token.kind)
+    }
+
+    fn curr_precedence(&self) -> Precedence {
+        Precedence::get_precedence_for_token(self.curr_token.kind)
+    }
+}
+
+/// Parses integer literal validated by lexer: 42, 1_000, 0x1F, 0o17, 0b1010.
+/// Returns None if it doesn't fit into integer
+fn parse_integer(literal: &str) -> Option<i64> {
+    let literal = literal.replace('_', "");
+
+    let (digits, radix) = match literal.get(..2) {
+        Some("0x" | "0X") => (&literal[2..], 16),
+        Some("0o" | "0O") => (&literal[2..], 8),
+        Some("0b" | "0B") => (&literal[2..], 2),
+        _ => (&literal[..], 10),
+    };
+
+    i64::from_str_radix(digits, radix).ok()
+}
+
+/// Parses input which is expected to be valid. Used by tests of evaluator, compiler and vm
+#[cfg(test)]
+pub(crate) fn parse(input: &str) -> Program {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+    program
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{ast::Node, Expression, Statement},
+        lexer,
+        token::Kind,
+    };
+
+    use super::{ParseError, Parser};
+
+    #[test]
+    fn test_let_statements() {
+        let tests = vec![
+            ("let x = 5;", "x", "5"),
+            ("let y = 10", "y", "10"),
+            ("let foo = bar + 999;", "foo", "(bar + 999)"),
+        ];
+
+        for (input, expected_name, expected_value) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            assert_eq!(
+                program.statements.len(),
+                1,
+                "program has not enough statements"
+            );
+
+            let stmt = program.statements.first().unwrap();
+
+            assert_eq!(stmt.token_literal(), "let");
+
+            let stmt = match stmt {
+                Statement::LetStatement(v) => v,
+                _ => panic!("statement not LetStatement"),
+            };
+
+            assert_eq!(stmt.name.value, expected_name);
+            assert_eq!(stmt.name.token_literal(), expected_name);
+
+            let value = stmt.value.as_ref().expect("value not Some");
+
+            assert_eq!(value.to_str(), expected_value);
+        }
+    }
+
+    #[test]
+    fn test_return_statement() {
+        let tests = vec![
+            ("return 5;", Some("5")),
+            ("return foo * 2", Some("(foo * 2)")),
+            ("return;", None),
+        ];
+
+        for (input, expected_value) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            assert_eq!(
+                program.statements.len(),
+                1,
+                "program has not enough statements"
+            );
+
+            let stmt = program.statements.first().unwrap();
+
+            assert_eq!(stmt.token_literal(), "return");
+
+            let stmt = match stmt {
+                Statement::ReturnStatement(v) => v,
+                _ => panic!("statement not ReturnStatement"),
+            };
+
+            let value = stmt.return_value.as_ref().map(|v| v.to_str());
+
+            assert_eq!(value.as_deref(), expected_value);
+        }
+    }
+
+    #[test]
+    fn test_statement_missing_semicolon() {
+        let tests = vec![
+            (
+                "let x = 5 let y = 10;",
+                "1:11: expected next token: Semicolon. Got token: Let",
+            ),
+            (
+                "return 5 6;",
+                "1:10: expected next token: Semicolon. Got token: Number",
+            ),
+        ];
+
+        for (input, expected_error) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            let errors = parser.errors();
+
+            assert!(
+                errors.iter().any(|e| e.to_string() == expected_error),
+                "expected error {:?}. Got: {:?}",
+                expected_error,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_error_kinds() {
+        let tests = vec![
+            ("let 5 = x;", "E0001"),
+            ("let x = ;", "E0002"),
+            ("let x = @;", "E0101"),
+            ("99999999999999999999", "E0004"),
+            ("function() { 1", "E0005"),
+            ("let x = 0x;", "E0105"),
+            ("let x = 0b102;", "E0106"),
+            ("let x = 1_;", "E0107"),
+        ];
+
+        for (input, expected_code) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            let error = parser.errors().first().expect("no errors");
+
+            assert_eq!(error.code(), expected_code, "input: {}", input);
+        }
+
+        let lexer = lexer::Lexer::new("let 5 = x;");
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        match &parser.errors()[0] {
+            ParseError::UnexpectedToken {
+                expected,
+                found,
+                span,
+            } => {
+                assert_eq!(*expected, Kind::Ident);
+                assert_eq!(*found, Kind::Number);
+                assert_eq!((span.line, span.column), (1, 5));
+            }
+            other => panic!("error is not UnexpectedToken. Got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_identifier_expression() {
+        let input = "foobar;";
+
+        let lexer = lexer::Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program has not enough statements"
+        );
+
+        let stmt = program.statements.first().unwrap();
+
+        let stmt = match stmt {
+            Statement::ExpressionStatement(v) => v,
+            _ => panic!("statement not ExpressionStatement"),
+        };
+
+        let expr = stmt.expression.as_ref().expect("expression not Some");
+
+        let ident = match expr {
+            Expression::Identifier(v) => v,
+            _ => panic!("expression not Identifier"),
+        };
+
+        assert_eq!(ident.value, "foobar");
+        assert_eq!(ident.token_literal(), "foobar");
+    }
+
+    #[test]
+    fn test_integer_literal_expression() {
+        let input = "5;";
+
+        let lexer = lexer::Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program has not enough statements"
+        );
+
+        let stmt = program.statements.first().unwrap();
+
+        let stmt = match stmt {
+            Statement::ExpressionStatement(v) => v,
+            _ => panic!("statement not ExpressionStatement"),
+        };
+
+        let expr = stmt.expression.as_ref().expect("expression not Some");
+
+        let literal = match expr {
+            Expression::IntegerLiteral(v) => v,
+            _ => panic!("expression not IntegerLiteral"),
+        };
+
+        assert_eq!(literal.value, 5);
+        assert_eq!(literal.token_literal(), "5");
+    }
+
+    #[test]
+    fn test_integer_literal_forms() {
+        let tests = vec![
+            ("0x1F", 31),
+            ("0o17", 15),
+            ("0b1010", 10),
+            ("1_000_000", 1_000_000),
+            ("0x7FFF_FFFF_FFFF_FFFF", i64::MAX),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            let expr = match &program.statements[0] {
+                Statement::ExpressionStatement(v) => v.expression.as_ref().unwrap(),
+                _ => panic!("statement not ExpressionStatement"),
+            };
+
+            let literal = match expr {
+                Expression::IntegerLiteral(v) => v,
+                _ => panic!("expression not IntegerLiteral"),
+            };
+
+            assert_eq!(literal.value, expected, "input: {}", input);
+            assert_eq!(literal.to_str(), input);
+        }
+
+        let lexer = lexer::Lexer::new("0x8000_0000_0000_0000");
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(parser.errors()[0].code(), "E0004");
+    }
+
+    #[test]
+    fn test_float_literal_expression() {
+        let tests = vec![("2.75;", 2.75), ("1e-9", 1e-9), ("2.5E+3", 2500.0)];
+
+        for (input, expected) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            let expr = match &program.statements[0] {
+                Statement::ExpressionStatement(v) => v.expression.as_ref().unwrap(),
+                _ => panic!("statement not ExpressionStatement"),
+            };
+
+            let literal = match expr {
+                Expression::FloatLiteral(v) => v,
+                _ => panic!("expression not FloatLiteral"),
+            };
+
+            assert_eq!(literal.value, expected);
+            assert_eq!(literal.to_str(), input.trim_end_matches(';'));
+        }
+    }
+
+    #[test]
+    fn test_prefix_expression() {
+        let tests = vec![("!5", "!", 5), ("-10", "-", 10)];
+
+        for (input, expected_prefix, expected_right) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            for error in parser.errors() {
+                println!("ERROR: {}", error);
+            }
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            assert_eq!(
+                program.statements.len(),
+                1,
+                "program has not enough statements"
+            );
+
+            let stmt = program.statements.first().unwrap();
+
+            let stmt = match stmt {
+                Statement::ExpressionStatement(v) => v,
+                _ => panic!("statement not ExpressionStatement"),
+            };
+
+            let expr = stmt.expression.as_ref().expect("expression not Some");
+
+            let prefix_expr = match expr {
+                Expression::PrefixExpression(v) => v,
+                _ => panic!("expression not PrefixExpression"),
+            };
+
+            assert_eq!(prefix_expr.operator, expected_prefix);
+
+            // test integer literal
+            if let Some(right_expr) = prefix_expr.right.as_deref() {
+                let int_literal = match right_expr {
+                    Expression::IntegerLiteral(t) => t,
+                    _ => panic!("not integer literal"),
+                };
+
+                assert_eq!(int_literal.value, expected_right);
+            }
+        }
+    }
+
+    #[test]
+    fn test_infix_expression() {
+        let tests = vec![
             ("5 + 5;", 5, "+", 5),
             ("5 - 10;", 5, "-", 10),
             ("5 * 5;", 5, "*", 5),
@@ -500,7 +1154,100 @@ mod test {
             ("5 != 5;", 5, "!=", 5),
         ];
 
-        for (input, expected_left, expected_op, expected_right) in tests {
+        for (input, expected_left, expected_op, expected_right) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            for error in parser.errors() {
+                println!("ERROR: {}", error);
+            }
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            assert_eq!(
+                program.statements.len(),
+                1,
+                "program has not enough statements"
+            );
+
+            let stmt = program.statements.first().unwrap();
+
+            let stmt = match stmt {
+                Statement::ExpressionStatement(v) => v,
+                _ => panic!("statement not ExpressionStatement"),
+            };
+
+            let expr = stmt.expression.as_ref().expect("expression not Some");
+
+            let infix_expr = match expr {
+                Expression::InfixExpression(v) => v,
+                _ => panic!("expression not InfixExpression"),
+            };
+
+            // test integer literal
+            if let Some(left_expr) = infix_expr.left.as_deref() {
+                let int_literal = match left_expr {
+                    Expression::IntegerLiteral(t) => t,
+                    _ => panic!("not integer literal"),
+                };
+
+                assert_eq!(int_literal.value, expected_left);
+            }
+
+            assert_eq!(infix_expr.operator, expected_op);
+
+            // test integer literal
+            if let Some(right_expr) = infix_expr.right.as_deref() {
+                let int_literal = match right_expr {
+                    Expression::IntegerLiteral(t) => t,
+                    _ => panic!("not integer literal"),
+                };
+
+                assert_eq!(int_literal.value, expected_right);
+            }
+        }
+    }
+
+    #[test]
+    fn test_boolean_expression() {
+        let tests = vec![("true;", true), ("false;", false)];
+
+        for (input, expected) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            let stmt = match program.statements.first().unwrap() {
+                Statement::ExpressionStatement(v) => v,
+                _ => panic!("statement not ExpressionStatement"),
+            };
+
+            let boolean = match stmt.expression.as_ref().expect("expression not Some") {
+                Expression::Boolean(v) => v,
+                _ => panic!("expression not Boolean"),
+            };
+
+            assert_eq!(boolean.value, expected);
+        }
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let tests = vec![
+            ("if (x < y) { x }", "(x < y)", "x", None),
+            ("if (x < y) { x } else { y }", "(x < y)", "x", Some("y")),
+            (
+                "if (x) { let z = x; z } else { return y; }",
+                "x",
+                "let z = x;z",
+                Some("return y;"),
+            ),
+        ];
+
+        for (input, expected_condition, expected_consequence, expected_alternative) in tests {
             let lexer = lexer::Lexer::new(input);
             let mut parser = Parser::new(lexer);
             let program = parser.parse_program();
@@ -517,41 +1264,423 @@ mod test {
                 "program has not enough statements"
             );
 
-            let stmt = program.statements.first().unwrap();
+            let stmt = match program.statements.first().unwrap() {
+                Statement::ExpressionStatement(v) => v,
+                _ => panic!("statement not ExpressionStatement"),
+            };
 
-            let stmt = match stmt {
+            let if_expr = match stmt.expression.as_ref().expect("expression not Some") {
+                Expression::IfExpression(v) => v,
+                _ => panic!("expression not IfExpression"),
+            };
+
+            assert_eq!(if_expr.condition.to_str(), expected_condition);
+
+            let consequence: String = if_expr
+                .consequence
+                .statements
+                .iter()
+                .map(|s| s.to_str())
+                .collect();
+
+            assert_eq!(consequence, expected_consequence);
+
+            let alternative = if_expr.alternative.as_ref().map(|block| {
+                block
+                    .statements
+                    .iter()
+                    .map(|s| s.to_str())
+                    .collect::<String>()
+            });
+
+            assert_eq!(alternative.as_deref(), expected_alternative);
+        }
+    }
+
+    #[test]
+    fn test_function_literal() {
+        let tests = vec![
+            ("function() {};", vec![], ""),
+            ("function(x) { x };", vec!["x"], "x"),
+            (
+                "function(x, y, z) { x + y; };",
+                vec!["x", "y", "z"],
+                "(x + y)",
+            ),
+        ];
+
+        for (input, expected_params, expected_body) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            let stmt = match program.statements.first().unwrap() {
                 Statement::ExpressionStatement(v) => v,
                 _ => panic!("statement not ExpressionStatement"),
             };
 
-            let expr = stmt.expression.as_ref().expect("expression not Some");
+            let function = match stmt.expression.as_ref().expect("expression not Some") {
+                Expression::FunctionLiteral(v) => v,
+                _ => panic!("expression not FunctionLiteral"),
+            };
 
-            let infix_expr = match expr {
-                Expression::InfixExpression(v) => v,
-                _ => panic!("expression not InfixExpression"),
+            let params: Vec<&str> = function
+                .parameters
+                .iter()
+                .map(|p| p.value.as_str())
+                .collect();
+
+            assert_eq!(params, expected_params);
+
+            let body: String = function
+                .body
+                .statements
+                .iter()
+                .map(|s| s.to_str())
+                .collect();
+
+            assert_eq!(body, expected_body);
+        }
+    }
+
+    #[test]
+    fn test_call_expression() {
+        let input = "add(1, 2 * 3, 4 + 5);";
+
+        let lexer = lexer::Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program has not enough statements"
+        );
+
+        let stmt = match program.statements.first().unwrap() {
+            Statement::ExpressionStatement(v) => v,
+            _ => panic!("statement not ExpressionStatement"),
+        };
+
+        let call = match stmt.expression.as_ref().expect("expression not Some") {
+            Expression::CallExpression(v) => v,
+            _ => panic!("expression not CallExpression"),
+        };
+
+        assert_eq!(call.function.to_str(), "add");
+
+        let arguments: Vec<String> = call.arguments.iter().map(|a| a.to_str()).collect();
+
+        assert_eq!(arguments, vec!["1", "(2 * 3)", "(4 + 5)"]);
+    }
+
+    #[test]
+    fn test_string_literal_expression() {
+        let input = r#""hello\n\u{1F600} world";"#;
+
+        let lexer = lexer::Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+        let stmt = match program.statements.first().unwrap() {
+            Statement::ExpressionStatement(v) => v,
+            _ => panic!("statement not ExpressionStatement"),
+        };
+
+        let literal = match stmt.expression.as_ref().expect("expression not Some") {
+            Expression::StringLiteral(v) => v,
+            _ => panic!("expression not StringLiteral"),
+        };
+
+        assert_eq!(literal.value, "hello\n\u{1F600} world");
+    }
+
+    #[test]
+    fn test_illegal_token() {
+        let lexer = lexer::Lexer::new(r#"let x = "abc"#);
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 1);
+
+        let error = &parser.errors()[0];
+
+        assert!(matches!(
+            error,
+            ParseError::Lex(lexer::LexError::UnterminatedString { .. })
+        ));
+        assert_eq!(error.code(), "E0102");
+        assert_eq!(error.to_string(), "1:9: unterminated string");
+    }
+
+    #[test]
+    fn test_array_literal() {
+        let tests = vec![
+            ("[]", vec![]),
+            ("[1, 2 * 2, 3 + 3]", vec!["1", "(2 * 2)", "(3 + 3)"]),
+            ("[[1], \"a\"]", vec!["[1]", "\"a\""]),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            let stmt = match program.statements.first().unwrap() {
+                Statement::ExpressionStatement(v) => v,
+                _ => panic!("statement not ExpressionStatement"),
             };
 
-            // test integer literal
-            if let Some(left_expr) = infix_expr.left.as_deref() {
-                let int_literal = match left_expr {
-                    Expression::IntegerLiteral(t) => t,
-                    _ => panic!("not integer literal"),
-                };
+            let array = match stmt.expression.as_ref().expect("expression not Some") {
+                Expression::ArrayLiteral(v) => v,
+                _ => panic!("expression not ArrayLiteral"),
+            };
 
-                assert_eq!(int_literal.value, expected_left);
-            }
+            let elements: Vec<String> = array.elements.iter().map(|e| e.to_str()).collect();
 
-            assert_eq!(infix_expr.operator, expected_op);
+            assert_eq!(elements, expected);
+        }
+    }
 
-            // test integer literal
-            if let Some(right_expr) = infix_expr.right.as_deref() {
-                let int_literal = match right_expr {
-                    Expression::IntegerLiteral(t) => t,
-                    _ => panic!("not integer literal"),
-                };
+    #[test]
+    fn test_index_expression() {
+        let input = "myArray[1 + 1]";
 
-                assert_eq!(int_literal.value, expected_right);
+        let lexer = lexer::Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+        let stmt = match program.statements.first().unwrap() {
+            Statement::ExpressionStatement(v) => v,
+            _ => panic!("statement not ExpressionStatement"),
+        };
+
+        let index = match stmt.expression.as_ref().expect("expression not Some") {
+            Expression::IndexExpression(v) => v,
+            _ => panic!("expression not IndexExpression"),
+        };
+
+        assert_eq!(index.left.to_str(), "myArray");
+        assert_eq!(index.index.to_str(), "(1 + 1)");
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let tests = vec![
+            ("{}", vec![]),
+            (
+                r#"{"one": 1, "two": 2, "three": 3}"#,
+                vec![("\"one\"", "1"), ("\"two\"", "2"), ("\"three\"", "3")],
+            ),
+            (
+                r#"{1: true, "a" + "b": 10 - 8, false: [1],}"#,
+                vec![
+                    ("1", "true"),
+                    ("(\"a\" + \"b\")", "(10 - 8)"),
+                    ("false", "[1]"),
+                ],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+            let stmt = match program.statements.first().unwrap() {
+                Statement::ExpressionStatement(v) => v,
+                _ => panic!("statement not ExpressionStatement"),
+            };
+
+            let hash = match stmt.expression.as_ref().expect("expression not Some") {
+                Expression::HashLiteral(v) => v,
+                _ => panic!("expression not HashLiteral"),
+            };
+
+            let pairs: Vec<(String, String)> = hash
+                .pairs
+                .iter()
+                .map(|(k, v)| (k.to_str(), v.to_str()))
+                .collect();
+
+            let expected: Vec<(String, String)> = expected
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+
+            assert_eq!(pairs, expected);
+        }
+    }
+
+    #[test]
+    fn test_hash_literal_errors() {
+        let tests = vec![
+            (
+                "{1 2}",
+                "1:4: expected next token: Colon. Got token: Number",
+            ),
+            (
+                "{1: 2 3: 4}",
+                "1:7: expected next token: Comma. Got token: Number",
+            ),
+        ];
+
+        for (input, expected_error) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            let errors = parser.errors();
+
+            assert!(
+                errors.iter().any(|e| e.to_string() == expected_error),
+                "expected error {:?}. Got: {:?}",
+                expected_error,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let input = "let x = -a + add(b, 2);\nif (x) { [1][0] } else { {1: 2} }\nreturn x;";
+
+        let lexer = lexer::Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 0, "errors should be zero");
+
+        let spans: Vec<(usize, usize, usize, usize)> = program
+            .statements
+            .iter()
+            .map(|s| {
+                let span = s.span();
+                (span.start, span.end, span.line, span.column)
+            })
+            .collect();
+
+        assert_eq!(spans, vec![(0, 23, 1, 1), (24, 57, 2, 1), (58, 67, 3, 1)]);
+
+        let value = match &program.statements[0] {
+            Statement::LetStatement(v) => v.value.as_ref().unwrap(),
+            _ => panic!("statement not LetStatement"),
+        };
+
+        let infix = match value {
+            Expression::InfixExpression(v) => v,
+            _ => panic!("expression not InfixExpression"),
+        };
+
+        let left = infix.left.as_ref().unwrap().span();
+        let right = infix.right.as_ref().unwrap().span();
+
+        assert_eq!(&input[infix.span.start..infix.span.end], "-a + add(b, 2)");
+        assert_eq!(&input[left.start..left.end], "-a");
+        assert_eq!(&input[right.start..right.end], "add(b, 2)");
+        assert_eq!((right.line, right.column), (1, 14));
+    }
+
+    #[test]
+    fn test_unterminated_block() {
+        let lexer = lexer::Lexer::new("if (x) { x");
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 1);
+
+        let error = &parser.errors()[0];
+
+        assert!(matches!(error, ParseError::UnterminatedBlock { .. }));
+        assert_eq!(
+            error.to_string(),
+            "1:11: unterminated block: expected Rbrace. Got token: Eof"
+        );
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let input = "let x = 5 + * 3 + 2;\nlet = 10;\nlet y = x;\nfunction() { ) 1; return 2 }\nif (x) { 1 + }; let z = 3;";
+
+        let lexer = lexer::Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        let errors: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "1:13: no prefix parse function for Asterisk found",
+                "2:5: expected next token: Ident. Got token: Assign",
+                "4:14: no prefix parse function for Rparen found",
+                "5:14: no prefix parse function for Rbrace found",
+            ]
+        );
+
+        let statements: Vec<String> = program.statements.iter().map(|s| s.to_str()).collect();
+
+        assert_eq!(
+            statements,
+            vec![
+                "let x = <error>;",
+                "<error>;",
+                "let y = x;",
+                "function() { <error>return 2; }",
+                "if x { <error> }",
+                "let z = 3;",
+            ]
+        );
+
+        match &program.statements[1] {
+            Statement::Error(stmt) => {
+                assert_eq!(&input[stmt.span.start..stmt.span.end], "let = 10;")
             }
+            other => panic!("statement not Error. Got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_prefix_operand() {
+        let tests = vec![
+            (
+                "-;",
+                "<error>",
+                "1:2: no prefix parse function for Semicolon found",
+            ),
+            (
+                "!;",
+                "<error>",
+                "1:2: no prefix parse function for Semicolon found",
+            ),
+            (
+                "let a = -;\nlet b = 1;",
+                "let a = <error>;let b = 1;",
+                "1:10: no prefix parse function for Semicolon found",
+            ),
+        ];
+
+        for (input, expected, error) in tests {
+            let lexer = lexer::Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            let errors: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+
+            assert_eq!(errors, vec![error], "input: {}", input);
+            assert_eq!(program.to_str(), expected, "input: {}", input);
         }
     }
 
@@ -561,6 +1690,19 @@ mod test {
             ("-a * b", "((-a) * b)"),
             ("!-a", "(!(-a))"),
             ("a + b + c", "((a + b) + c)"),
+            ("a % b * c", "((a % b) * c)"),
+            ("a + b % c", "(a + (b % c))"),
+            ("a ** b ** c", "(a ** (b ** c))"),
+            ("a * b ** c", "(a * (b ** c))"),
+            ("a ** b * c", "((a ** b) * c)"),
+            ("-a ** b", "(-(a ** b))"),
+            ("a ** -b", "(a ** (-b))"),
+            ("a ** b[0] ** c", "(a ** ((b[0]) ** c))"),
+            ("a <= b == b >= a", "((a <= b) == (b >= a))"),
+            ("a || b && c", "(a || (b && c))"),
+            ("a && b || c && d", "((a && b) || (c && d))"),
+            ("a == b && c != d", "((a == b) && (c != d))"),
+            ("!a || -b < c", "((!a) || ((-b) < c))"),
             ("a + b - c", "((a + b) - c)"),
             ("a * b * c", "((a * b) * c)"),
             ("a * b / c", "((a * b) / c)"),
@@ -573,6 +1715,39 @@ mod test {
                 "3 + 4 * 5 == 3 * 1 + 4 * 5",
                 "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
             ),
+            ("true", "true"),
+            ("false", "false"),
+            ("3 > 5 == false", "((3 > 5) == false)"),
+            ("3 < 5 == true", "((3 < 5) == true)"),
+            ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
+            ("(5 + 5) * 2", "((5 + 5) * 2)"),
+            ("2 / (5 + 5)", "(2 / (5 + 5))"),
+            ("-(5 + 5)", "(-(5 + 5))"),
+            ("!(true == true)", "(!(true == true))"),
+            (r#""a\tb" + "\"c\"""#, r#"("a\tb" + "\"c\"")"#),
+            ("a + add(b * c) + d", "((a + add((b * c))) + d)"),
+            (
+                "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
+                "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))",
+            ),
+            (
+                "add(a + b + c * d / f + g)",
+                "add((((a + b) + ((c * d) / f)) + g))",
+            ),
+            (
+                "a * [1, 2, 3, 4][b * c] * d",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+            ),
+            (
+                "add(a * b[2], b[1], 2 * [1, 2][1])",
+                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+            ),
+            ("f(x)[0]", "(f(x)[0])"),
+            ("{1: 2 + 3}[1] * 2", "(({1: (2 + 3)}[1]) * 2)"),
+            (
+                "let add = function(x, y) { return x + y; }; add(1, 2 * 3);",
+                "let add = function(x, y) { return (x + y); };add(1, (2 * 3))",
+            ),
         ];
 
         for (input, expected) in tests {
@@ -1,31 +1,71 @@
-use std::io::{self, BufRead, BufReader, Write};
+use std::{
+    cell::RefCell,
+    io::{self, BufRead, IsTerminal, Write},
+    rc::Rc,
+};
 
-use crate::{lexer::Lexer, token::token};
+use crate::{
+    diagnostics::{Diagnostic, Renderer},
+    evaluator,
+    lexer::Lexer,
+    object::{Environment, Object},
+    parser::Parser,
+};
 
 const PROMPT: &str = "> ";
+const FILE_NAME: &str = "<repl>";
 
 pub fn start() {
     println!("Welcome to Monkey programming language v0.0.1.");
 
-    let input = BufReader::new(io::stdin());
-    let mut output = io::stdout().lock();
+    let color = io::stdout().is_terminal();
+
+    run(
+        io::stdin().lock(),
+        Rc::new(RefCell::new(io::stdout())),
+        color,
+    );
+}
+
+/// Reads lines from input and evaluates them one by one.
+/// Prompt, results, errors and program output (puts) are written to the output
+pub fn run(input: impl BufRead, output: Rc<RefCell<dyn Write>>, color: bool) {
+    let env = Rc::new(RefCell::new(Environment::with_output(Rc::clone(&output))));
 
     let mut scanner = input.lines();
 
     loop {
-        write!(output, "{}", PROMPT).unwrap();
-        output.flush().unwrap();
+        {
+            let mut output = output.borrow_mut();
+            write!(output, "{}", PROMPT).unwrap();
+            output.flush().unwrap();
+        }
 
         if let Some(line) = scanner.next() {
             match line {
                 Ok(line) => {
-                    let mut lexer = Lexer::new(&line);
+                    let lexer = Lexer::new(&line);
+                    let mut parser = Parser::new(lexer);
 
-                    let mut tok = lexer.next_token();
+                    let program = parser.parse_program();
+                    let renderer = Renderer::new(FILE_NAME, &line, color);
 
-                    while tok.kind != token::Kind::Eof {
-                        writeln!(output, "{:?}", tok).unwrap();
-                        tok = lexer.next_token();
+                    if !parser.errors().is_empty() {
+                        for error in parser.errors() {
+                            let diagnostic = renderer.render(&Diagnostic::from(error));
+                            write!(output.borrow_mut(), "{}", diagnostic).unwrap();
+                        }
+                        continue;
+                    }
+
+                    let evaluated = evaluator::eval(&program, &env);
+
+                    match &evaluated {
+                        Object::Error(error) => {
+                            let diagnostic = renderer.render(&Diagnostic::from(error));
+                            write!(output.borrow_mut(), "{}", diagnostic).unwrap();
+                        }
+                        _ => writeln!(output.borrow_mut(), "{}", evaluated.inspect()).unwrap(),
                     }
                 }
                 Err(_) => return,
@@ -35,3 +75,45 @@ pub fn start() {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, io::Write, rc::Rc};
+
+    use super::run;
+
+    #[test]
+    fn test_run() {
+        let input = "let x = 2;\nputs(x * 5)\nlet y = ;\nx + true\nlet z = 1 @ 2;\n".as_bytes();
+
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let sink: Rc<RefCell<dyn Write>> = output.clone();
+
+        run(input, sink, false);
+
+        let output = String::from_utf8(output.borrow().clone()).unwrap();
+
+        let expected = "\
+> null
+> 10
+null
+> error[E0002]: no prefix parse function for Semicolon found
+ --> <repl>:1:9
+  |
+1 | let y = ;
+  |         ^
+> error: type mismatch: INTEGER + BOOLEAN
+ --> <repl>:1:1
+  |
+1 | x + true
+  | ^^^^^^^^
+> error[E0101]: unexpected character: '@'
+ --> <repl>:1:11
+  |
+1 | let z = 1 @ 2;
+  |           ^
+> ";
+
+        assert_eq!(output, expected);
+    }
+}
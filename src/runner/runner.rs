@@ -0,0 +1,268 @@
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, IsTerminal, Write},
+    process::ExitCode,
+    rc::Rc,
+};
+
+use crate::{
+    ast::ast::Program,
+    compiler::{disassemble, Compiler},
+    diagnostics::{Diagnostic, Renderer},
+    evaluator,
+    lexer::Lexer,
+    object::{Environment, Object},
+    parser::Parser,
+};
+
+/// Runs script file. Program output goes to stdout, errors are rendered to stderr
+pub fn run_file(path: &str) -> ExitCode {
+    let source = match read_file(path) {
+        Some(source) => source,
+        None => return ExitCode::FAILURE,
+    };
+
+    let output = Rc::new(RefCell::new(io::stdout()));
+    let color = io::stderr().is_terminal();
+
+    if run_source(path, &source, output, &mut io::stderr(), color) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Parses and evaluates source. Returns false if there were parse or runtime errors
+pub fn run_source(
+    file_name: &str,
+    source: &str,
+    output: Rc<RefCell<dyn Write>>,
+    errors: &mut dyn Write,
+    color: bool,
+) -> bool {
+    let renderer = Renderer::new(file_name, source, color);
+
+    let program = match parse(source, &renderer, errors) {
+        Some(program) => program,
+        None => return false,
+    };
+
+    let env = Rc::new(RefCell::new(Environment::with_output(output)));
+
+    match evaluator::eval(&program, &env) {
+        Object::Error(error) => {
+            write!(errors, "{}", renderer.render(&Diagnostic::from(&error))).unwrap();
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Compiles script file and prints its bytecode listing to stdout
+pub fn disassemble_file(path: &str) -> ExitCode {
+    let source = match read_file(path) {
+        Some(source) => source,
+        None => return ExitCode::FAILURE,
+    };
+
+    let color = io::stderr().is_terminal();
+
+    if disassemble_source(path, &source, &mut io::stdout(), &mut io::stderr(), color) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Parses and compiles source, then writes disassembled bytecode to output.
+/// Returns false if there were parse or compile errors
+pub fn disassemble_source(
+    file_name: &str,
+    source: &str,
+    output: &mut dyn Write,
+    errors: &mut dyn Write,
+    color: bool,
+) -> bool {
+    let renderer = Renderer::new(file_name, source, color);
+
+    let program = match parse(source, &renderer, errors) {
+        Some(program) => program,
+        None => return false,
+    };
+
+    let mut compiler = Compiler::new();
+
+    if let Err(error) = compiler.compile(&program) {
+        write!(errors, "{}", renderer.render(&Diagnostic::from(&error))).unwrap();
+        return false;
+    }
+
+    write!(output, "{}", disassemble(&compiler.bytecode())).unwrap();
+
+    true
+}
+
+fn read_file(path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(source) => Some(source),
+        Err(error) => {
+            eprintln!("error: could not read {}: {}", path, error);
+            None
+        }
+    }
+}
+
+/// Parses source. Errors are rendered and None is returned if there were any
+fn parse(source: &str, renderer: &Renderer, errors: &mut dyn Write) -> Option<Program> {
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program();
+
+    if !parser.errors().is_empty() {
+        for error in parser.errors() {
+            write!(errors, "{}", renderer.render(&Diagnostic::from(error))).unwrap();
+        }
+        return None;
+    }
+
+    Some(program)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, io::Write, rc::Rc};
+
+    use super::{disassemble_source, run_source};
+
+    fn run(source: &str) -> (bool, String, String) {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let sink: Rc<RefCell<dyn Write>> = output.clone();
+        let mut errors = Vec::new();
+
+        let ok = run_source("main.mk", source, sink, &mut errors, false);
+
+        let output = String::from_utf8(output.borrow().clone()).unwrap();
+        let errors = String::from_utf8(errors).unwrap();
+
+        (ok, output, errors)
+    }
+
+    #[test]
+    fn test_run_source() {
+        let source = "let greet = function(name) { puts(\"hi \" + name) };\ngreet(\"monkey\");\n";
+
+        assert_eq!(
+            run(source),
+            (true, "hi monkey\n".to_string(), "".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_source_with_runtime_error() {
+        let source = "puts(1);\nlet f = function(x) {\n  x + y\n};\nf(1);\n";
+
+        let expected_errors = "\
+error: identifier not found: y
+ --> main.mk:3:7
+  |
+3 |   x + y
+  |       ^
+";
+
+        assert_eq!(
+            run(source),
+            (false, "1\n".to_string(), expected_errors.to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_source_with_lex_error() {
+        let source = "let s = \"a\\q\";\nputs(s);\n";
+
+        let expected_errors = "\
+error[E0103]: invalid escape sequence
+ --> main.mk:1:11
+  |
+1 | let s = \"a\\q\";
+  |           ^^
+  = help: supported escapes are \\n, \\t, \\r, \\\", \\\\ and \\u{...}
+";
+
+        assert_eq!(
+            run(source),
+            (false, "".to_string(), expected_errors.to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_source_with_invalid_number() {
+        let source = "let x = 0b102;\n";
+
+        let expected_errors = "\
+error[E0106]: invalid digit '2' in base 2 number
+ --> main.mk:1:13
+  |
+1 | let x = 0b102;
+  |             ^
+";
+
+        assert_eq!(
+            run(source),
+            (false, "".to_string(), expected_errors.to_string())
+        );
+    }
+
+    #[test]
+    fn test_disassemble_source() {
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+
+        let ok = disassemble_source("main.mk", "1 + 2;", &mut output, &mut errors, false);
+
+        let expected = "\
+== main ==
+0000    1 OpConstant 0 (1)
+0003    | OpConstant 1 (2)
+0006    | OpAdd
+0007    | OpPop
+
+== constants ==
+0000 INTEGER 1
+0001 INTEGER 2
+";
+
+        assert!(ok);
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_disassemble_source_with_compile_error() {
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+
+        let ok = disassemble_source(
+            "main.mk",
+            "let a = 1;
+a + b;
+",
+            &mut output,
+            &mut errors,
+            false,
+        );
+
+        let expected_errors = "\
+error[E0202]: undefined variable: b
+ --> main.mk:2:5
+  |
+2 | a + b;
+  |     ^
+";
+
+        assert!(!ok);
+        assert!(output.is_empty());
+        assert_eq!(String::from_utf8(errors).unwrap(), expected_errors);
+    }
+}
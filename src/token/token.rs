@@ -1,7 +1,52 @@
+use std::{borrow::Cow, fmt};
+
+/// Token borrows its literal from the source where possible
 #[derive(Default, Debug, Clone)]
-pub struct Token {
+pub struct Token<'src> {
     pub kind: Kind,
-    pub literal: String,
+    pub literal: Cow<'src, str>,
+    pub span: Span,
+    pub leading_trivia: Vec<Trivia<'src>>, // collected only if lexer preserves trivia
+}
+
+/// Whitespace or comment before token
+#[derive(PartialEq, Debug, Clone)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: Cow<'src, str>,
+    pub span: Span,
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+}
+
+/// Location of the source code fragment
+#[derive(PartialEq, Eq, Default, Debug, Copy, Clone)]
+pub struct Span {
+    pub start: usize,  // byte offset of the first character
+    pub end: usize,    // byte offset after the last character
+    pub line: usize,   // line of the first character, starting from 1
+    pub column: usize, // column of the first character in characters, starting from 1
+}
+
+impl Span {
+    /// Returns span which starts at self and ends at the end of other span
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: self.end.max(other.end),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
 #[derive(PartialEq, Default, Debug, Copy, Clone)]
@@ -13,27 +58,38 @@ pub enum Kind {
     // identifiers, literals
     Ident,
     Number,
+    Float,
+    String,
 
     // operators
     Assign,
     Plus,
     Minus,
     Asterisk,
+    Percent,
+    Power,
     Slash,
     Bang,
     Lt,
     Gt,
     Eq,
     Neq,
+    LtEq,
+    GtEq,
+    And,
+    Or,
 
     // delimiters
     Comma,
     Semicolon,
+    Colon,
 
     Lparen,
     Rparen,
     Lbrace,
     Rbrace,
+    Lbracket,
+    Rbracket,
 
     // keywords
     Function,
@@ -46,15 +102,19 @@ pub enum Kind {
 }
 
 impl Kind {
-    pub fn to_str(&self) -> &'static str {
+    pub fn to_str(self) -> &'static str {
         match self {
             Kind::Eof => "EOF",
             Kind::Illegal => "Illegal",
             Kind::Ident => "Identifier",
             Kind::Number => "number",
+            Kind::Float => "float",
+            Kind::String => "string",
             Kind::Assign => "=",
             Kind::Plus => "+",
             Kind::Asterisk => "*",
+            Kind::Percent => "%",
+            Kind::Power => "**",
             Kind::Minus => "-",
             Kind::Slash => "/",
             Kind::Bang => "!",
@@ -62,12 +122,19 @@ impl Kind {
             Kind::Gt => ">",
             Kind::Eq => "==",
             Kind::Neq => "!=",
+            Kind::LtEq => "<=",
+            Kind::GtEq => ">=",
+            Kind::And => "&&",
+            Kind::Or => "||",
             Kind::Comma => ",",
             Kind::Semicolon => ";",
+            Kind::Colon => ":",
             Kind::Lparen => "(",
             Kind::Rparen => ")",
             Kind::Lbrace => "{",
             Kind::Rbrace => "}",
+            Kind::Lbracket => "[",
+            Kind::Rbracket => "]",
             Kind::Function => "function",
             Kind::Let => "let",
             Kind::Return => "return",
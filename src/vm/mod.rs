@@ -0,0 +1,3 @@
+pub mod vm;
+
+pub use self::vm::*;
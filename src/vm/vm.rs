@@ -0,0 +1,641 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crate::{
+    compiler::{read_u16, Bytecode, Opcode},
+    evaluator::{eval_infix_expression, eval_prefix_expression, MAX_CALL_DEPTH},
+    object::{Builtin, Closure, CompiledFunction, Object, RuntimeError, BUILTINS},
+    token::Span,
+};
+
+const STACK_SIZE: usize = 2048;
+const MAX_FRAMES: usize = MAX_CALL_DEPTH;
+
+/// Call of the closure. Locals live on the stack starting at base pointer
+struct Frame {
+    closure: Rc<Closure>,
+    ip: usize,           // offset of the next instruction
+    base_pointer: usize, // stack position of the first local
+}
+
+/// Stack virtual machine executing compiled bytecode.
+/// Operators share implementation with evaluator, so both backends produce the same results.
+///
+/// Known differences:
+/// - closure copies values of captured locals when it is created, while evaluator keeps
+///   reference to the environment. So local redefined after closure is created is seen
+///   by evaluator, but not by virtual machine
+/// - function called before the global it refers to is defined reads null instead of
+///   failing with "identifier not found"
+pub struct Vm {
+    constants: Vec<Object>,
+    globals: Vec<Object>,
+
+    stack: Vec<Object>,
+    frames: Vec<Frame>,
+    last_popped: Object, // value of the last expression statement
+
+    output: Rc<RefCell<dyn Write>>, // sink for builtins like puts
+}
+
+impl Vm {
+    pub fn new(bytecode: Bytecode) -> Self {
+        Vm::with_output(bytecode, Rc::new(RefCell::new(io::stdout())))
+    }
+
+    /// Creates virtual machine which writes program output to the given sink instead of stdout
+    pub fn with_output(bytecode: Bytecode, output: Rc<RefCell<dyn Write>>) -> Self {
+        // main program is executed as function without parameters
+        let main = CompiledFunction {
+            instructions: bytecode.instructions,
+            source_map: bytecode.source_map,
+            num_locals: 0,
+            num_parameters: 0,
+        };
+
+        let main = Closure {
+            function: Rc::new(main),
+            free: Vec::new(),
+        };
+
+        let mut frames = Vec::with_capacity(MAX_FRAMES);
+        frames.push(Frame {
+            closure: Rc::new(main),
+            ip: 0,
+            base_pointer: 0,
+        });
+
+        Vm {
+            constants: bytecode.constants,
+            globals: Vec::new(),
+            stack: Vec::with_capacity(STACK_SIZE),
+            frames,
+            last_popped: Object::Null,
+            output,
+        }
+    }
+
+    /// Returns value of the last executed expression statement, like evaluator does
+    pub fn last_popped_stack_elem(&self) -> &Object {
+        &self.last_popped
+    }
+
+    /// Runs program. Runtime error gets location of the instruction which caused it
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        self.execute().map_err(|mut error| {
+            if error.span.is_none() {
+                error.span = self.current_span();
+            }
+
+            error
+        })
+    }
+
+    fn execute(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().expect("no frame");
+            let closure = Rc::clone(&frame.closure);
+            let base_pointer = frame.base_pointer;
+
+            let ins = &closure.function.instructions;
+            let mut ip = frame.ip;
+
+            // functions always end with return, so only main program can run out of instructions
+            if ip >= ins.len() {
+                return Ok(());
+            }
+
+            let byte = ins[ip];
+            let opcode = Opcode::from_byte(byte)
+                .ok_or_else(|| RuntimeError::new(format!("unknown opcode: {}", byte)))?;
+
+            ip += 1;
+
+            let width: usize = opcode.operand_widths().iter().sum();
+            frame.ip = ip + width;
+
+            match opcode {
+                Opcode::Constant => {
+                    let index = read_u16(&ins[ip..]) as usize;
+                    self.push(self.constants[index].clone())?;
+                }
+                Opcode::Pop => {
+                    self.last_popped = self.pop();
+                }
+                Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Div
+                | Opcode::Mod
+                | Opcode::Pow
+                | Opcode::Equal
+                | Opcode::NotEqual
+                | Opcode::LessThan
+                | Opcode::GreaterThan
+                | Opcode::LessThanOrEqual
+                | Opcode::GreaterThanOrEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+
+                    let result = eval_infix_expression(infix_operator(opcode), left, right);
+                    self.push_result(result)?;
+                }
+                Opcode::Minus => {
+                    let right = self.pop();
+                    self.push_result(eval_prefix_expression("-", right))?;
+                }
+                Opcode::Bang => {
+                    let right = self.pop();
+                    self.push_result(eval_prefix_expression("!", right))?;
+                }
+                Opcode::True => self.push(Object::TRUE)?,
+                Opcode::False => self.push(Object::FALSE)?,
+                Opcode::Null => self.push(Object::Null)?,
+                Opcode::Jump => {
+                    self.current_frame().ip = read_u16(&ins[ip..]) as usize;
+                }
+                Opcode::JumpNotTruthy => {
+                    let target = read_u16(&ins[ip..]) as usize;
+
+                    if !self.pop().is_truthy() {
+                        self.current_frame().ip = target;
+                    }
+                }
+                Opcode::GetGlobal => {
+                    let index = read_u16(&ins[ip..]) as usize;
+                    let value = self.globals.get(index).cloned().unwrap_or(Object::Null);
+
+                    self.push(value)?;
+                }
+                Opcode::SetGlobal => {
+                    let index = read_u16(&ins[ip..]) as usize;
+
+                    if index >= self.globals.len() {
+                        self.globals.resize(index + 1, Object::Null);
+                    }
+
+                    self.globals[index] = self.pop();
+
+                    // let statement evaluates to null, so program ending with it returns null
+                    self.last_popped = Object::Null;
+                }
+                Opcode::GetLocal => {
+                    let index = ins[ip] as usize;
+                    self.push(self.stack[base_pointer + index].clone())?;
+                }
+                Opcode::SetLocal => {
+                    let index = ins[ip] as usize;
+                    self.stack[base_pointer + index] = self.pop();
+                }
+                Opcode::GetBuiltin => {
+                    let (name, function) = BUILTINS[ins[ip] as usize];
+                    self.push(Object::Builtin(Builtin::new(name, function)))?;
+                }
+                Opcode::GetFree => {
+                    let index = ins[ip] as usize;
+                    self.push(closure.free[index].clone())?;
+                }
+                Opcode::Closure => {
+                    let index = read_u16(&ins[ip..]) as usize;
+                    let num_free = ins[ip + 2] as usize;
+
+                    self.push_closure(index, num_free)?;
+                }
+                Opcode::CurrentClosure => {
+                    self.push(Object::Closure(Rc::clone(&closure)))?;
+                }
+                Opcode::Call => {
+                    let num_arguments = ins[ip] as usize;
+                    self.call(num_arguments)?;
+                }
+                Opcode::ReturnValue => {
+                    let value = self.pop();
+                    self.frames.pop();
+
+                    // return from main program stops it, like in evaluator
+                    if self.frames.is_empty() {
+                        self.last_popped = value;
+                        return Ok(());
+                    }
+
+                    // remove locals and called function
+                    self.stack.truncate(base_pointer - 1);
+                    self.push(value)?;
+                }
+            }
+        }
+    }
+
+    /// Calls function which is below its arguments on the stack
+    fn call(&mut self, num_arguments: usize) -> Result<(), RuntimeError> {
+        let callee = self.stack[self.stack.len() - 1 - num_arguments].clone();
+
+        match callee {
+            Object::Closure(closure) => {
+                let function = &closure.function;
+
+                if function.num_parameters != num_arguments {
+                    return Err(RuntimeError::new(format!(
+                        "wrong number of arguments: want={}, got={}",
+                        function.num_parameters, num_arguments
+                    )));
+                }
+
+                if self.frames.len() >= MAX_FRAMES {
+                    return Err(RuntimeError::new("stack overflow".to_string()));
+                }
+
+                // arguments become the first locals
+                let base_pointer = self.stack.len() - num_arguments;
+
+                for _ in num_arguments..function.num_locals {
+                    self.push(Object::Null)?;
+                }
+
+                self.frames.push(Frame {
+                    closure,
+                    ip: 0,
+                    base_pointer,
+                });
+
+                Ok(())
+            }
+            Object::Builtin(builtin) => {
+                let arguments = self.stack.split_off(self.stack.len() - num_arguments);
+                self.pop();
+
+                let result = builtin.call(&arguments, &mut *self.output.borrow_mut());
+                self.push_result(result)
+            }
+            _ => Err(RuntimeError::new(format!(
+                "not a function: {}",
+                callee.object_type()
+            ))),
+        }
+    }
+
+    /// Wraps compiled function from constant pool into closure, capturing values from the stack
+    fn push_closure(&mut self, index: usize, num_free: usize) -> Result<(), RuntimeError> {
+        let function = match &self.constants[index] {
+            Object::CompiledFunction(function) => Rc::clone(function),
+            other => {
+                return Err(RuntimeError::new(format!(
+                    "not a function: {}",
+                    other.object_type()
+                )))
+            }
+        };
+
+        let free = self.stack.split_off(self.stack.len() - num_free);
+
+        self.push(Object::Closure(Rc::new(Closure { function, free })))
+    }
+
+    /// Returns source location of the last started instruction of the current frame
+    fn current_span(&self) -> Option<Span> {
+        let frame = self.frames.last()?;
+        let source_map = &frame.closure.function.source_map;
+
+        // ip already points after the instruction
+        let index = source_map.partition_point(|(position, _)| *position < frame.ip);
+
+        index.checked_sub(1).map(|index| source_map[index].1)
+    }
+
+    fn current_frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("no frame")
+    }
+
+    fn push(&mut self, object: Object) -> Result<(), RuntimeError> {
+        if self.stack.len() >= STACK_SIZE {
+            return Err(RuntimeError::new("stack overflow".to_string()));
+        }
+
+        self.stack.push(object);
+
+        Ok(())
+    }
+
+    /// Pushes result of operator, unless it is an error
+    fn push_result(&mut self, result: Object) -> Result<(), RuntimeError> {
+        match result {
+            Object::Error(error) => Err(error),
+            result => self.push(result),
+        }
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().expect("stack underflow")
+    }
+}
+
+/// Returns source operator of binary opcode
+fn infix_operator(opcode: Opcode) -> &'static str {
+    match opcode {
+        Opcode::Add => "+",
+        Opcode::Sub => "-",
+        Opcode::Mul => "*",
+        Opcode::Div => "/",
+        Opcode::Mod => "%",
+        Opcode::Pow => "**",
+        Opcode::Equal => "==",
+        Opcode::NotEqual => "!=",
+        Opcode::LessThan => "<",
+        Opcode::GreaterThan => ">",
+        Opcode::LessThanOrEqual => "<=",
+        Opcode::GreaterThanOrEqual => ">=",
+        _ => unreachable!("{} is not binary operator", opcode.name()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        compiler::Compiler,
+        evaluator,
+        object::{Environment, Object},
+        parser::parse,
+    };
+
+    use super::Vm;
+
+    /// Runs input on the virtual machine. Runtime error is returned as error object
+    fn run(input: &str) -> Object {
+        let program = parse(input);
+
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).expect("compile failed");
+
+        let mut vm = Vm::new(compiler.bytecode());
+
+        match vm.run() {
+            Ok(()) => vm.last_popped_stack_elem().clone(),
+            Err(error) => Object::Error(error),
+        }
+    }
+
+    fn eval(input: &str) -> Object {
+        let program = parse(input);
+
+        let env = Rc::new(RefCell::new(Environment::new()));
+
+        evaluator::eval(&program, &env)
+    }
+
+    #[test]
+    fn test_integer_arithmetic() {
+        let tests = vec![
+            ("1", 1),
+            ("1 + 2", 3),
+            ("1 - 2", -1),
+            ("4 / 2", 2),
+            ("50 / 2 * 2 + 10 - 5", 55),
+            ("5 * (2 + 10)", 60),
+            ("-50 + 100 + -50", 0),
+            ("10 % 3", 1),
+            ("2 ** 3 ** 2", 512),
+            ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(run(input), Object::Integer(expected), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_boolean_expressions() {
+        let tests = vec![
+            ("true", true),
+            ("1 < 2", true),
+            ("1 > 2", false),
+            ("1 <= 1", true),
+            ("2 >= 3", false),
+            ("1 == 1", true),
+            ("1 != 1", false),
+            ("true != false", true),
+            ("(1 < 2) == true", true),
+            ("!true", false),
+            ("!!5", true),
+            ("!(if (false) { 5; })", true),
+            ("true && false", false),
+            ("false || 1 < 2", true),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(run(input), Object::Boolean(expected), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let tests = vec![
+            ("if (true) { 10 }", Object::Integer(10)),
+            ("if (1 < 2) { 10 } else { 20 }", Object::Integer(10)),
+            ("if (1 > 2) { 10 } else { 20 }", Object::Integer(20)),
+            ("if (1 > 2) { 10 }", Object::Null),
+            ("if (false) { 10 }", Object::Null),
+            ("if (true) { }", Object::Null),
+            (
+                "if ((if (false) { 10 })) { 10 } else { 20 }",
+                Object::Integer(20),
+            ),
+            ("", Object::Null),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(run(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_global_let_statements() {
+        let tests = vec![
+            ("let one = 1; one", 1),
+            ("let one = 1; let two = 2; one + two", 3),
+            ("let one = 1; let two = one + one; one + two", 3),
+            ("let x = 1; let x = x + 1; x", 2),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(run(input), Object::Integer(expected), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_calling_functions() {
+        let tests = vec![
+            ("let f = function() { 5 + 10 }; f()", Object::Integer(15)),
+            ("function(a, b) { a * b }(3, 4)", Object::Integer(12)),
+            (
+                "let sum = function(a, b) { let c = a + b; c }; sum(1, 2) + sum(3, 4)",
+                Object::Integer(10),
+            ),
+            (
+                "let early = function() { return 1; 2 }; early()",
+                Object::Integer(1),
+            ),
+            ("let empty = function() { }; empty()", Object::Null),
+            (
+                "let g = 10; let f = function() { let g = 1; g }; f() + g",
+                Object::Integer(11),
+            ),
+            ("return 7; 8", Object::Integer(7)),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(run(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_closures() {
+        let tests = vec![
+            (
+                "let adder = function(a) { function(b) { a + b } }; adder(2)(3)",
+                5,
+            ),
+            (
+                "let f = function(a) { function(b) { function(c) { a + b + c } } }; f(1)(2)(3)",
+                6,
+            ),
+            (
+                "let wrap = function(x) { let inner = function() { x * 2 }; inner() }; wrap(21)",
+                42,
+            ),
+            (
+                "let fib = function(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+                610,
+            ),
+            (
+                "let outer = function() { let count = function(n) { if (n == 0) { 0 } else { count(n - 1) } }; count(3) }; outer()",
+                0,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(run(input), Object::Integer(expected), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_forward_references() {
+        let tests = vec![
+            (
+                "let f = function() { g() }; let g = function() { 1 }; f()",
+                Object::Integer(1),
+            ),
+            (
+                "let even = function(n) { if (n == 0) { true } else { odd(n - 1) } };
+                 let odd = function(n) { if (n == 0) { false } else { even(n - 1) } };
+                 even(10)",
+                Object::Boolean(true),
+            ),
+            (
+                "let f = function() { function() { x * 2 } }; let x = 21; f()()",
+                Object::Integer(42),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(run(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_builtin_functions() {
+        let output = Rc::new(RefCell::new(Vec::new()));
+
+        let program = parse(r#"puts("hello", len("four")); let len = 1; len"#);
+
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).expect("compile failed");
+
+        let mut vm = Vm::with_output(compiler.bytecode(), output.clone());
+        vm.run().expect("run failed");
+
+        assert_eq!(vm.last_popped_stack_elem(), &Object::Integer(1));
+        assert_eq!(String::from_utf8(output.take()).unwrap(), "hello\n4\n");
+    }
+
+    #[test]
+    fn test_largest_operands() {
+        let constants: String = (0..65536).map(|i| format!("{};", i)).collect();
+        assert_eq!(run(&constants), Object::Integer(65535));
+
+        let jump = format!("if (false) {{ {} }} else {{ 42 }}", "true; ".repeat(32763));
+        assert_eq!(run(&jump), Object::Integer(42));
+
+        let lets: String = (0..256).map(|i| format!("let a{} = {}; ", i, i)).collect();
+        let locals = format!("function() {{ {} a0 + a255 }}()", lets);
+        assert_eq!(run(&locals), Object::Integer(255));
+    }
+
+    #[test]
+    fn test_deep_recursion() {
+        let input = "let f = function(n) { f(n + 1) }; f(0)";
+
+        match run(input) {
+            Object::Error(error) => {
+                assert_eq!(error.message, "stack overflow");
+
+                let span = error.span.expect("no span");
+                assert_eq!(&input[span.start..span.end], "f(n + 1)");
+            }
+            other => panic!("object is not Error. Got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_same_results_as_evaluator() {
+        let tests = vec![
+            "1 + 2 * 3 - 4 / 2",
+            "-(5 - 10) % 3",
+            "2 ** 62 * 4",
+            "1.5 * 2 + 1",
+            "7 / 2.0 >= 3",
+            r#""mon" + "key""#,
+            r#""a" == "a""#,
+            "if (1 < 2 && 2 < 3) { 1 } else { 2 }",
+            "if (0) { 1 } else { 2 }",
+            "5 + true",
+            "-true",
+            "true + false",
+            "10 / 0",
+            "2 ** -1",
+            r#""a" - "b""#,
+            "false && 1 / 0",
+            "true || 1 / 0",
+            "1 / 0 || true",
+            "let f = function(a, b) { a + b }; f(1)",
+            "let x = 1; x(2)",
+            r#"len("abc", "d")"#,
+            "len(1)",
+            "let f = function(a) { a }; f + 1",
+            "let a = 1; let f = function() { a }; let a = 2; f()",
+            "let f = function(n) { f(n + 1) }; f(0);",
+            "1; let a = 2;",
+            "let a = 1; a; let b = a;",
+            "let f = function() { let a = 1; a }; f(); let g = f;",
+            "let f = function(x) {\n  x + true\n}; f(1)",
+            "let f = function() { len(1) }; 1 + f()",
+            "let f = function() { g() }; let g = function() { 1 }; f()",
+            "let even = function(n) { if (n == 0) { true } else { odd(n - 1) } }; let odd = function(n) { if (n == 0) { false } else { even(n - 1) } }; odd(7)",
+        ];
+
+        for input in tests {
+            assert_eq!(run(input), eval(input), "input: {}", input);
+        }
+
+        // closure copies captured locals, so it doesn't see redefinition made after it is created
+        let input =
+            "let f = function() { let a = 1; let g = function() { a }; let a = 2; g() }; f()";
+
+        assert_eq!(eval(input), Object::Integer(2));
+        assert_eq!(run(input), Object::Integer(1));
+    }
+}